@@ -0,0 +1,220 @@
+use crate::{Edge, Graph, GraphError, Node, Order};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+const MAGIC: &[u8; 6] = b"OPHOIS";
+const VERSION: u8 = 1;
+
+/// Layout, little endian: magic, version, header lines, nodes (id, latitude, longitude)
+/// then links (source index, target index, edges (id, length)), counts and string lengths as u32
+pub fn write_binary(
+    graph: &Graph,
+    writer: &mut impl Write,
+    order: Order,
+) -> Result<(), GraphError> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[VERSION])?;
+    write_u32(writer, graph.header.len())?;
+    for line in &graph.header {
+        write_string(writer, line)?;
+    }
+    let nodes = graph.sorted_nodes(order);
+    let mut indexes: HashMap<&str, usize> = HashMap::new();
+    write_u32(writer, nodes.len())?;
+    for (index, node) in nodes.iter().enumerate() {
+        indexes.insert(&node.id, index);
+        write_string(writer, &node.id)?;
+        writer.write_all(&coordinate(&node.latitude)?.to_le_bytes())?;
+        writer.write_all(&coordinate(&node.longitude)?.to_le_bytes())?;
+    }
+    let pairs = graph.sorted_pairs(order);
+    write_u32(writer, pairs.len())?;
+    for (source, target) in pairs {
+        write_u32(writer, indexes[source.as_str()])?;
+        write_u32(writer, indexes[target.as_str()])?;
+        let edges = graph.sorted_edges(source, target, order);
+        write_u32(writer, edges.len())?;
+        for edge in edges {
+            write_string(writer, &edge.id)?;
+            writer.write_all(&edge.length.to_le_bytes())?;
+        }
+    }
+    writer.flush()?;
+    return Ok(());
+}
+
+pub fn read_binary(reader: &mut impl Read) -> Result<Graph, GraphError> {
+    let mut magic = [0; 6];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(GraphError::InvalidRecord(
+            "not an ophois binary graph".to_string(),
+        ));
+    }
+    let mut version = [0; 1];
+    reader.read_exact(&mut version)?;
+    if version[0] != VERSION {
+        return Err(GraphError::InvalidRecord(format!(
+            "unsupported binary graph version {}",
+            version[0]
+        )));
+    }
+    let mut graph = Graph::new();
+    for _ in 0..read_u32(reader)? {
+        graph.header.push(read_string(reader)?);
+    }
+    let mut ids: Vec<String> = Vec::new();
+    for _ in 0..read_u32(reader)? {
+        let id = read_string(reader)?;
+        let latitude = read_f64(reader)?;
+        let longitude = read_f64(reader)?;
+        graph.insert_checked_node(Node {
+            id: id.clone(),
+            latitude: latitude.to_string(),
+            longitude: longitude.to_string(),
+            neighbours: Vec::new(),
+        })?;
+        ids.push(id);
+    }
+    for _ in 0..read_u32(reader)? {
+        let source = node_id(&ids, read_u32(reader)?)?;
+        let target = node_id(&ids, read_u32(reader)?)?;
+        for _ in 0..read_u32(reader)? {
+            let edge = Edge {
+                id: read_string(reader)?,
+                length: read_f64(reader)?,
+            };
+            graph.insert_edge(source, target, edge)?;
+        }
+    }
+    return Ok(graph);
+}
+
+fn coordinate(value: &str) -> Result<f64, GraphError> {
+    return value
+        .parse()
+        .map_err(|_| GraphError::InvalidRecord(format!("invalid coordinate {}", value)));
+}
+
+fn node_id(ids: &[String], index: u32) -> Result<&str, GraphError> {
+    return ids
+        .get(index as usize)
+        .map(|id| id.as_str())
+        .ok_or_else(|| GraphError::InvalidRecord(format!("unknown node index {}", index)));
+}
+
+fn write_u32(writer: &mut impl Write, value: usize) -> Result<(), GraphError> {
+    let value = u32::try_from(value)
+        .map_err(|_| GraphError::InvalidRecord(format!("{} exceeds binary graph limits", value)))?;
+    writer.write_all(&value.to_le_bytes())?;
+    return Ok(());
+}
+
+fn write_string(writer: &mut impl Write, value: &str) -> Result<(), GraphError> {
+    write_u32(writer, value.len())?;
+    writer.write_all(value.as_bytes())?;
+    return Ok(());
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, GraphError> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    return Ok(u32::from_le_bytes(bytes));
+}
+
+fn read_f64(reader: &mut impl Read) -> Result<f64, GraphError> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    return Ok(f64::from_le_bytes(bytes));
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String, GraphError> {
+    let length = read_u32(reader)? as usize;
+    let mut bytes: Vec<u8> = Vec::new();
+    reader
+        .by_ref()
+        .take(length as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.len() != length {
+        return Err(GraphError::InvalidRecord(
+            "truncated binary graph".to_string(),
+        ));
+    }
+    return String::from_utf8(bytes)
+        .map_err(|_| GraphError::InvalidRecord("invalid utf-8 string".to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_binary_round_trip() {
+        let graph = Graph::_from("# ophois 0.3.0 extract separator='␟'\n3761637488␟48.8275416␟2.3486683\n3761637486␟48.8275249␟2.348704\n3761637489␟48.8275453␟2.348698\n2268836829␟48.8276001␟2.3486802\n3761637489␟3761637488\n3761637489␟2268836829␟2268836829-3761637489␟5.5\n3761637489␟2268836829␟2268836829-3761637489~1␟12.25\n3761637489␟3761637486", '␟').unwrap();
+        let mut bytes: Vec<u8> = Vec::new();
+        write_binary(&graph, &mut bytes, Order::Insertion).unwrap();
+        let copy = read_binary(&mut bytes.as_slice()).unwrap();
+        assert_eq!(copy.header, graph.header);
+        assert_eq!(copy.nodes.len(), graph.nodes.len());
+        for node in graph.nodes.values() {
+            let other = copy.get_node(&node.id).unwrap();
+            assert_eq!(other.point().unwrap(), node.point().unwrap());
+            let mut neighbours = node.neighbours.clone();
+            let mut other_neighbours = other.neighbours.clone();
+            neighbours.sort();
+            other_neighbours.sort();
+            assert_eq!(other_neighbours, neighbours);
+        }
+        assert_eq!(copy.edges, graph.edges);
+        let ids = |graph: &Graph| -> Vec<String> {
+            graph
+                .sorted_nodes(Order::Insertion)
+                .iter()
+                .map(|node| node.id.clone())
+                .collect()
+        };
+        assert_eq!(ids(&copy), ids(&graph));
+    }
+
+    #[test]
+    fn test_read_binary_errors() {
+        assert!(matches!(
+            read_binary(&mut "3761637488␟48.8275416␟2.3486683".as_bytes()),
+            Err(GraphError::InvalidRecord(_))
+        ));
+        let mut bytes: Vec<u8> = Vec::new();
+        write_binary(&Graph::new(), &mut bytes, Order::Natural).unwrap();
+        bytes[6] = VERSION + 1;
+        assert!(matches!(
+            read_binary(&mut bytes.as_slice()),
+            Err(GraphError::InvalidRecord(_))
+        ));
+        bytes[6] = VERSION;
+        assert!(matches!(
+            read_binary(&mut &bytes[..8]),
+            Err(GraphError::Io(_))
+        ));
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.push(VERSION);
+        write_u32(&mut bytes, 1).unwrap();
+        bytes.extend_from_slice(&u32::MAX.to_le_bytes());
+        bytes.extend_from_slice(b"# ophois");
+        assert!(matches!(
+            read_binary(&mut bytes.as_slice()),
+            Err(GraphError::InvalidRecord(_))
+        ));
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.push(VERSION);
+        write_u32(&mut bytes, 0).unwrap();
+        write_u32(&mut bytes, 2).unwrap();
+        for latitude in [48.8275416, 48.8275453] {
+            write_string(&mut bytes, "3761637488").unwrap();
+            bytes.extend_from_slice(&f64::to_le_bytes(latitude));
+            bytes.extend_from_slice(&f64::to_le_bytes(2.3486683));
+        }
+        write_u32(&mut bytes, 0).unwrap();
+        assert!(matches!(
+            read_binary(&mut bytes.as_slice()),
+            Err(GraphError::InvalidRecord(_))
+        ));
+    }
+}
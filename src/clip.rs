@@ -0,0 +1,361 @@
+use crate::{get_point_from_line, haversine_distance};
+use crate::{Edge, Graph, GraphError, Node, Operation, Order, Point};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Zone of a graph to keep
+#[derive(Debug, PartialEq)]
+pub enum Area {
+    BoundingBox {
+        south: f64,
+        west: f64,
+        north: f64,
+        east: f64,
+    },
+    /// Polygons made of rings of longitude latitude points, the first ring of each is its outline
+    /// and the next ones its holes
+    Polygons(Vec<Vec<Vec<Point>>>),
+    /// Radius is expressed in meters
+    Around { center: Point, radius: f64 },
+}
+
+impl Area {
+    /// Parses south,west,north,east as overpass does
+    pub fn from_bbox(bbox: &str) -> Result<Area, GraphError> {
+        return match numbers(bbox)?[..] {
+            [south, west, north, east] if south <= north && west <= east => Ok(Area::BoundingBox {
+                south,
+                west,
+                north,
+                east,
+            }),
+            _ => Err(GraphError::InvalidRecord(format!(
+                "invalid bbox {}, expected south,west,north,east",
+                bbox
+            ))),
+        };
+    }
+
+    /// Parses latitude,longitude,radius
+    pub fn from_around(around: &str) -> Result<Area, GraphError> {
+        return match numbers(around)?[..] {
+            [latitude, longitude, radius] if radius >= 0.0 => Ok(Area::Around {
+                center: Point {
+                    x: longitude,
+                    y: latitude,
+                },
+                radius,
+            }),
+            _ => Err(GraphError::InvalidRecord(format!(
+                "invalid around {}, expected latitude,longitude,radius",
+                around
+            ))),
+        };
+    }
+
+    /// Collects the Polygon and MultiPolygon geometries of a GeoJSON geometry, feature or feature collection
+    pub fn from_geojson(geojson: &str) -> Result<Area, GraphError> {
+        let value: Value = serde_json::from_str(geojson)
+            .map_err(|error| GraphError::InvalidRecord(format!("invalid GeoJSON {}", error)))?;
+        let mut polygons: Vec<Vec<Vec<Point>>> = Vec::new();
+        collect_polygons(&value, &mut polygons)?;
+        if polygons.is_empty() {
+            return Err(GraphError::InvalidRecord(
+                "GeoJSON holds no Polygon nor MultiPolygon".to_string(),
+            ));
+        }
+        return Ok(Area::Polygons(polygons));
+    }
+
+    pub fn contains(&self, point: &Point) -> bool {
+        return match self {
+            Area::BoundingBox {
+                south,
+                west,
+                north,
+                east,
+            } => *south <= point.y && point.y <= *north && *west <= point.x && point.x <= *east,
+            Area::Polygons(polygons) => polygons.iter().any(|rings| {
+                // even-odd rule, crossing a hole outline leaves the polygon
+                rings.iter().filter(|ring| crosses(ring, point)).count() % 2 == 1
+            }),
+            Area::Around { center, radius } => haversine_distance(center, point) <= *radius,
+        };
+    }
+
+    /// Fraction of the segment from inside to outside where it leaves the area, found by bisection
+    fn exit(&self, inside: &Point, outside: &Point) -> f64 {
+        let (mut low, mut high) = (0.0, 1.0);
+        for _ in 0..64 {
+            let middle = (low + high) / 2.0;
+            if self.contains(&get_point_from_line(inside, outside, middle)) {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        return low;
+    }
+}
+
+/// What becomes of links with a node inside and a node outside of the area
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Boundary {
+    Drop,
+    /// The link is cut where it leaves the area, at a new node named after the edge, links with both
+    /// nodes outside are cut where they enter and leave it
+    Cut,
+}
+
+impl FromStr for Boundary {
+    type Err = String;
+
+    fn from_str(boundary: &str) -> Result<Boundary, String> {
+        return match boundary {
+            "drop" => Ok(Boundary::Drop),
+            "cut" => Ok(Boundary::Cut),
+            _ => Err(format!(
+                "unknown boundary {}, expected drop or cut",
+                boundary
+            )),
+        };
+    }
+}
+
+/// Keeps nodes within the area and links between them, links crossing the boundary are dropped
+/// or cut into an edge to a boundary node "{edge_id}:boundary" whose length is prorated. A link
+/// with both nodes outside which goes through the area is cut at "{edge_id}:boundary:1" and
+/// "{edge_id}:boundary:2", found by sampling the link so that a barely touched corner may be missed
+pub fn clip(graph: Graph, area: &Area, boundary: Boundary) -> Result<Graph, GraphError> {
+    let mut clipped = Graph::new();
+    clipped.header = graph.header.clone();
+    clipped.origins = graph.origins.clone();
+    for node in graph.sorted_nodes(Order::Insertion) {
+        if area.contains(&node.point()?) {
+            clipped.insert_node(Node {
+                id: node.id.clone(),
+                latitude: node.latitude.clone(),
+                longitude: node.longitude.clone(),
+                neighbours: Vec::new(),
+            });
+        }
+    }
+    for (u, v) in graph.sorted_pairs(Order::Insertion) {
+        let (u_inside, v_inside) = (clipped.nodes.contains_key(u), clipped.nodes.contains_key(v));
+        if u_inside && v_inside {
+            for edge in graph.sorted_edges(u, v, Order::Insertion) {
+                clipped.insert_edge(u, v, edge.clone())?;
+            }
+        } else if boundary == Boundary::Drop {
+            continue;
+        } else if u_inside || v_inside {
+            let (inside, outside) = if u_inside { (u, v) } else { (v, u) };
+            let (a, b) = (
+                graph.get_node(inside)?.point()?,
+                graph.get_node(outside)?.point()?,
+            );
+            let fraction = area.exit(&a, &b);
+            let point = get_point_from_line(&a, &b, fraction);
+            for edge in graph.sorted_edges(u, v, Order::Insertion) {
+                let node_id = format!("{}:boundary", edge.id);
+                insert_boundary(&mut clipped, &node_id, &point, &edge.id, &[u, v])?;
+                let cut = Edge {
+                    id: edge.id.clone(),
+                    length: edge.length * fraction,
+                };
+                clipped.insert_edge(inside, &node_id, cut)?;
+            }
+        } else {
+            let (a, b) = (graph.get_node(u)?.point()?, graph.get_node(v)?.point()?);
+            let samples = 64;
+            let middle = match (0..samples)
+                .map(|i| (i as f64 + 0.5) / samples as f64)
+                .find(|middle| area.contains(&get_point_from_line(&a, &b, *middle)))
+            {
+                Some(middle) => middle,
+                None => continue,
+            };
+            let inside = get_point_from_line(&a, &b, middle);
+            let entry = middle * (1.0 - area.exit(&inside, &a));
+            let exit = middle + (1.0 - middle) * area.exit(&inside, &b);
+            for edge in graph.sorted_edges(u, v, Order::Insertion) {
+                let ids = [
+                    format!("{}:boundary:1", edge.id),
+                    format!("{}:boundary:2", edge.id),
+                ];
+                for (node_id, fraction) in ids.iter().zip([entry, exit]) {
+                    let point = get_point_from_line(&a, &b, fraction);
+                    insert_boundary(&mut clipped, node_id, &point, &edge.id, &[u, v])?;
+                }
+                let cut = Edge {
+                    id: edge.id.clone(),
+                    length: edge.length * (exit - entry),
+                };
+                clipped.insert_edge(&ids[0], &ids[1], cut)?;
+            }
+        }
+    }
+    clipped
+        .origins
+        .retain(|node_id, _| clipped.nodes.contains_key(node_id));
+    return Ok(clipped);
+}
+
+/// Adds a node where an edge leaves the area, derived from both nodes of its link
+fn insert_boundary(
+    clipped: &mut Graph,
+    node_id: &str,
+    point: &Point,
+    edge_id: &str,
+    link: &[&str],
+) -> Result<(), GraphError> {
+    if clipped.nodes.contains_key(node_id) {
+        return Err(GraphError::InvalidRecord(format!(
+            "edge id {} is used by several links",
+            edge_id
+        )));
+    }
+    clipped.insert_node(Node {
+        id: node_id.to_string(),
+        longitude: point.x.to_string(),
+        latitude: point.y.to_string(),
+        neighbours: Vec::new(),
+    });
+    clipped.derive(node_id, Operation::Clip, link);
+    return Ok(());
+}
+
+fn numbers(list: &str) -> Result<Vec<f64>, GraphError> {
+    return list
+        .split(',')
+        .map(|number| {
+            number
+                .trim()
+                .parse()
+                .map_err(|_| GraphError::InvalidRecord(format!("invalid number {}", number)))
+        })
+        .collect();
+}
+
+/// Whether a ray going east from the point crosses the ring an odd number of times
+fn crosses(ring: &[Point], point: &Point) -> bool {
+    let mut inside = false;
+    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        if (a.y > point.y) != (b.y > point.y)
+            && point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y)
+        {
+            inside = !inside;
+        }
+    }
+    return inside;
+}
+
+fn collect_polygons(value: &Value, polygons: &mut Vec<Vec<Vec<Point>>>) -> Result<(), GraphError> {
+    match value["type"].as_str() {
+        Some("FeatureCollection") => {
+            for feature in value["features"].as_array().into_iter().flatten() {
+                collect_polygons(feature, polygons)?;
+            }
+        }
+        Some("Feature") => collect_polygons(&value["geometry"], polygons)?,
+        Some("GeometryCollection") => {
+            for geometry in value["geometries"].as_array().into_iter().flatten() {
+                collect_polygons(geometry, polygons)?;
+            }
+        }
+        Some("Polygon") => polygons.push(rings(&value["coordinates"])?),
+        Some("MultiPolygon") => {
+            for polygon in value["coordinates"].as_array().into_iter().flatten() {
+                polygons.push(rings(polygon)?);
+            }
+        }
+        _ => {}
+    }
+    return Ok(());
+}
+
+fn rings(value: &Value) -> Result<Vec<Vec<Point>>, GraphError> {
+    let invalid = || GraphError::InvalidRecord(format!("invalid polygon coordinates {}", value));
+    let mut rings: Vec<Vec<Point>> = Vec::new();
+    for ring in value.as_array().ok_or_else(invalid)? {
+        let mut points: Vec<Point> = Vec::new();
+        for position in ring.as_array().ok_or_else(invalid)? {
+            match (position[0].as_f64(), position[1].as_f64()) {
+                (Some(x), Some(y)) => points.push(Point { x, y }),
+                _ => return Err(invalid()),
+            }
+        }
+        rings.push(points);
+    }
+    return Ok(rings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const GRAPH: &str = "3758221295␟48.8275185␟2.3484976\n3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n2268836829␟48.8276001␟2.3486802\n3758221295␟3761637488\n3761637488␟3761637489\n3761637489␟2268836829";
+
+    #[test]
+    fn test_clip_areas() {
+        let bbox = Area::from_bbox("48.82752,2.3485,48.8276,2.3488").unwrap();
+        let around = Area::from_around("48.8275416,2.3486683,10").unwrap();
+        let polygon = Area::from_geojson(r#"{"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [[[2.3485, 48.82752], [2.3488, 48.82752], [2.3488, 48.8276], [2.3485, 48.8276], [2.3485, 48.82752]], [[2.34869, 48.82754], [2.34871, 48.82754], [2.34871, 48.82755], [2.34869, 48.82755], [2.34869, 48.82754]]]}}"#).unwrap();
+        for (area, nodes) in [(bbox, 2), (around, 3), (polygon, 1)] {
+            let graph = clip(Graph::_from(GRAPH, '␟').unwrap(), &area, Boundary::Drop).unwrap();
+            assert_eq!(graph.nodes.len(), nodes);
+            assert!(graph.check_invariants().is_empty());
+        }
+        assert!(Area::from_bbox("48.8276,2.3485,48.82752,2.3488").is_err());
+        assert!(Area::from_around("48.8275416,2.3486683").is_err());
+        assert!(Area::from_geojson(r#"{"type": "Point", "coordinates": [2.3, 48.8]}"#).is_err());
+    }
+
+    #[test]
+    fn test_clip_cut() {
+        let area = Area::from_bbox("48.82752,2.3485,48.8276,2.3488").unwrap();
+        let graph = Graph::_from(GRAPH, '␟').unwrap();
+        let length = graph.length("3758221295", "3761637488").unwrap();
+        let graph = clip(graph, &area, Boundary::Cut).unwrap();
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.edges_count(), 3);
+        let node = graph.get_node("3758221295-3761637488:boundary").unwrap();
+        assert!((node.point().unwrap().y - 48.82752).abs() < 1e-9);
+        let cut = graph.length("3761637488", &node.id).unwrap();
+        assert!(cut < length);
+        assert!((cut - graph.distance("3761637488", &node.id).unwrap()).abs() < 0.01);
+        assert!(graph.check_invariants().is_empty());
+    }
+
+    #[test]
+    fn test_clip_cut_through() {
+        let area = Area::from_bbox("48.82752,2.3485,48.8276,2.3488").unwrap();
+        let mut graph = Graph::_from(
+            "p␟48.8275␟2.3486\nq␟48.8277␟2.3487\nr␟48.8275␟2.3484\np␟q\np␟r",
+            '␟',
+        )
+        .unwrap();
+        graph.derive("p", Operation::Merge, &["a", "b"]);
+        let length = graph.length("p", "q").unwrap();
+        let clipped = clip(graph, &area, Boundary::Drop).unwrap();
+        assert!(clipped.nodes.is_empty() && clipped.origins.is_empty());
+        let mut graph = Graph::_from(
+            "p␟48.8275␟2.3486\nq␟48.8277␟2.3487\nr␟48.8275␟2.3484\np␟q\np␟r",
+            '␟',
+        )
+        .unwrap();
+        graph.derive("p", Operation::Merge, &["a", "b"]);
+        let graph = clip(graph, &area, Boundary::Cut).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges_count(), 1);
+        let entry = graph.get_node("p-q:boundary:1").unwrap();
+        let exit = graph.get_node("p-q:boundary:2").unwrap();
+        assert!((entry.point().unwrap().y - 48.82752).abs() < 1e-9);
+        assert!((exit.point().unwrap().y - 48.8276).abs() < 1e-9);
+        let cut = graph.length(&entry.id, &exit.id).unwrap();
+        assert!(cut < length);
+        assert!((cut - graph.distance(&entry.id, &exit.id).unwrap()).abs() < 0.01);
+        assert!(!graph.origins.contains_key("p"));
+        assert_eq!(graph.originals(&entry.id), vec!["a", "b", "q"]);
+        assert!(graph.check_invariants().is_empty());
+    }
+}
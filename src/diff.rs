@@ -0,0 +1,153 @@
+use crate::{haversine_distance, join_record, Graph, GraphError, Order};
+use std::collections::HashSet;
+
+/// Edge identified by the determinist pair of its nodes and its id
+pub type EdgeKey = (String, String, String);
+
+/// Changes from an old graph to a new one, in natural id order
+#[derive(Debug, PartialEq)]
+pub struct GraphDiff {
+    pub added_nodes: Vec<String>,
+    pub removed_nodes: Vec<String>,
+    /// Nodes found in both graphs with their displacement in meters
+    pub moved_nodes: Vec<(String, f64)>,
+    pub added_links: Vec<EdgeKey>,
+    pub removed_links: Vec<EdgeKey>,
+}
+
+impl GraphDiff {
+    pub fn is_empty(&self) -> bool {
+        return self.added_nodes.is_empty()
+            && self.removed_nodes.is_empty()
+            && self.moved_nodes.is_empty()
+            && self.added_links.is_empty()
+            && self.removed_links.is_empty();
+    }
+
+    pub fn summary(&self) -> String {
+        return format!(
+            "{} nodes added, {} removed, {} moved, {} links added, {} removed",
+            self.added_nodes.len(),
+            self.removed_nodes.len(),
+            self.moved_nodes.len(),
+            self.added_links.len(),
+            self.removed_links.len()
+        );
+    }
+
+    /// One change per line: +, - or ~ then node or link and their ids, escaped and separated by separator
+    pub fn records(&self, separator: char) -> Vec<String> {
+        let mut records: Vec<String> = Vec::new();
+        for node_id in &self.added_nodes {
+            records.push(join_record(&["+", "node", node_id], separator));
+        }
+        for node_id in &self.removed_nodes {
+            records.push(join_record(&["-", "node", node_id], separator));
+        }
+        for (node_id, displacement) in &self.moved_nodes {
+            let displacement = displacement.to_string();
+            records.push(join_record(
+                &["~", "node", node_id, &displacement],
+                separator,
+            ));
+        }
+        for (status, links) in [("+", &self.added_links), ("-", &self.removed_links)] {
+            for (source, target, id) in links {
+                records.push(join_record(
+                    &[status, "link", source, target, id],
+                    separator,
+                ));
+            }
+        }
+        return records;
+    }
+}
+
+/// Nodes are matched by id, those which moved by more than tolerance meters are reported
+pub fn diff(old: &Graph, new: &Graph, tolerance: f64) -> Result<GraphDiff, GraphError> {
+    let mut graph_diff = GraphDiff {
+        added_nodes: Vec::new(),
+        removed_nodes: Vec::new(),
+        moved_nodes: Vec::new(),
+        added_links: Vec::new(),
+        removed_links: Vec::new(),
+    };
+    for node in new.sorted_nodes(Order::Natural) {
+        if !old.nodes.contains_key(&node.id) {
+            graph_diff.added_nodes.push(node.id.clone());
+        }
+    }
+    for node in old.sorted_nodes(Order::Natural) {
+        match new.nodes.get(&node.id) {
+            None => graph_diff.removed_nodes.push(node.id.clone()),
+            Some(other) => {
+                let displacement = haversine_distance(&node.point()?, &other.point()?);
+                if displacement > tolerance {
+                    graph_diff.moved_nodes.push((node.id.clone(), displacement));
+                }
+            }
+        }
+    }
+    let (old_links, new_links) = (edge_keys(old), edge_keys(new));
+    let old_set: HashSet<&EdgeKey> = old_links.iter().collect();
+    let new_set: HashSet<&EdgeKey> = new_links.iter().collect();
+    graph_diff.added_links = new_links
+        .iter()
+        .filter(|key| !old_set.contains(key))
+        .cloned()
+        .collect();
+    graph_diff.removed_links = old_links
+        .iter()
+        .filter(|key| !new_set.contains(key))
+        .cloned()
+        .collect();
+    return Ok(graph_diff);
+}
+
+fn edge_keys(graph: &Graph) -> Vec<EdgeKey> {
+    let mut keys: Vec<EdgeKey> = Vec::new();
+    for (source, target) in graph.sorted_pairs(Order::Natural) {
+        for edge in graph.sorted_edges(source, target, Order::Natural) {
+            keys.push((source.clone(), target.clone(), edge.id.clone()));
+        }
+    }
+    return keys;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_diff() {
+        let old = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637486␟48.8275249␟2.348704\n3761637489␟48.8275453␟2.348698\n3761637489␟3761637488\n3761637489␟3761637486", '␟').unwrap();
+        let new = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637486␟48.8275351␟2.348701\n2268836829␟48.8276001␟2.3486802\n3761637488␟3761637486\n3761637488␟2268836829", '␟').unwrap();
+        let graph_diff = diff(&old, &new, 0.0).unwrap();
+        let key = |u: &str, v: &str| (u.to_string(), v.to_string(), format!("{}-{}", u, v));
+        assert_eq!(graph_diff.added_nodes, vec!["2268836829".to_string()]);
+        assert_eq!(graph_diff.removed_nodes, vec!["3761637489".to_string()]);
+        assert_eq!(graph_diff.moved_nodes.len(), 1);
+        assert_eq!(graph_diff.moved_nodes[0].0, "3761637486");
+        assert!((graph_diff.moved_nodes[0].1 - 1.155).abs() < 0.001);
+        assert_eq!(
+            graph_diff.added_links,
+            vec![
+                key("2268836829", "3761637488"),
+                key("3761637486", "3761637488")
+            ]
+        );
+        assert_eq!(
+            graph_diff.removed_links,
+            vec![
+                key("3761637486", "3761637489"),
+                key("3761637488", "3761637489")
+            ]
+        );
+        assert_eq!(
+            graph_diff.summary(),
+            "1 nodes added, 1 removed, 1 moved, 2 links added, 2 removed"
+        );
+        assert_eq!(graph_diff.records('␟')[0], "+␟node␟2268836829");
+        assert!(diff(&old, &new, 2.0).unwrap().moved_nodes.is_empty());
+        assert!(diff(&old, &old, 0.0).unwrap().is_empty());
+    }
+}
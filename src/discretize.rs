@@ -1,54 +1,62 @@
-use crate::Graph;
 use crate::Node;
-use crate::{determinist, get_point_from_line, haversine_distance};
+use crate::{determinist, get_point_from_line};
+use crate::{Edge, Graph, GraphError, Operation, Order};
 
-pub fn discretize(mut graph: Graph, delta: f64) -> Graph {
-    let links_clone = graph.links.clone();
-    for ((u, v), _) in links_clone {
-        if graph.contains_link((&u, &v)) && graph.contains_link((&v, &u)) {
-            let source = graph.get_node(&u).clone();
-            let target = graph.get_node(&v).clone();
-            let distance = haversine_distance(&source.point(), &target.point());
-            let part = (distance / delta) as u32;
-            if part > 1 {
-                graph.remove_link(&(u.clone(), v.clone()));
-                graph.remove_link(&(v.clone(), u.clone()));
-                let mut new_nodes = Vec::new();
-                let new_id = determinist(u.clone(), v.clone());
-                for i in 1..part {
-                    let point = get_point_from_line(
-                        &source.point(),
-                        &target.point(),
-                        i as f64 / part as f64,
-                    );
-                    let node = Node {
-                        id: format!("{}-{}:{}/{}", new_id.0, new_id.1, i, part),
-                        longitude: point.x.to_string(),
-                        latitude: point.y.to_string(),
-                        neighbours: Vec::new(),
-                    };
-                    new_nodes.push(node.id.clone());
-                    graph.insert_node(node);
-                }
-                for j in 1..part {
-                    let new_node_id = &new_nodes[(j - 1) as usize];
-                    let mut previous = format!("{}-{}:{}/{}", new_id.0, new_id.1, j - 1, part);
-                    let mut next = format!("{}-{}:{}/{}", new_id.0, new_id.1, j + 1, part);
-                    if j == 1 {
-                        previous = source.id.clone();
-                        graph.insert_link((previous.clone(), new_node_id.clone()));
-                    }
-                    if j == part - 1 {
-                        next = target.id.clone();
-                        graph.insert_link((next.clone(), new_node_id.clone()));
-                    }
-                    graph.insert_link((new_node_id.clone(), previous.clone()));
-                    graph.insert_link((new_node_id.clone(), next.clone()));
+/// Each parallel edge is split on its own, sub-edges share the length of the street they come from.
+/// Split nodes are named "{edge_id}:{i}/{n}", edge ids shared by several links are rejected
+pub fn discretize(mut graph: Graph, delta: f64) -> Result<Graph, GraphError> {
+    let pairs: Vec<(String, String)> = graph
+        .sorted_pairs(Order::Natural)
+        .into_iter()
+        .cloned()
+        .collect();
+    for (u, v) in pairs {
+        let edges = graph.get_edges(&u, &v).to_vec();
+        if !edges.iter().any(|edge| (edge.length / delta) as u32 > 1) {
+            continue;
+        }
+        let source = graph.get_node(&u)?.clone();
+        let target = graph.get_node(&v)?.clone();
+        graph.remove_link(&(u.clone(), v.clone()))?;
+        graph.remove_link(&(v.clone(), u.clone()))?;
+        for edge in edges {
+            let part = (edge.length / delta) as u32;
+            if part <= 1 {
+                graph.insert_edge(&u, &v, edge)?;
+                continue;
+            }
+            let mut chain = vec![source.id.clone()];
+            for i in 1..part {
+                let point =
+                    get_point_from_line(&source.point()?, &target.point()?, i as f64 / part as f64);
+                let node = Node {
+                    id: format!("{}:{}/{}", edge.id, i, part),
+                    longitude: point.x.to_string(),
+                    latitude: point.y.to_string(),
+                    neighbours: Vec::new(),
+                };
+                if graph.nodes.contains_key(&node.id) {
+                    return Err(GraphError::InvalidRecord(format!(
+                        "edge id {} is used by several links, node {} already exists",
+                        edge.id, node.id
+                    )));
                 }
+                chain.push(node.id.clone());
+                graph.derive(&node.id, Operation::Discretize, &[&u, &v]);
+                graph.insert_node(node);
+            }
+            chain.push(target.id.clone());
+            for j in 1..chain.len() {
+                let (previous, next) = determinist(chain[j - 1].clone(), chain[j].clone());
+                let sub_edge = Edge {
+                    id: graph.link_id(&previous, &next),
+                    length: edge.length / part as f64,
+                };
+                graph.insert_edge(&previous, &next, sub_edge)?;
             }
         }
     }
-    return graph;
+    return Ok(graph);
 }
 
 #[cfg(test)]
@@ -56,8 +64,9 @@ mod tests {
     use super::*;
     #[test]
     fn test_discretize() {
-        let mut graph = Graph::_from("92192237␟48.8275872␟2.3490245\n3761637486-3761637489␟48.8275351␟2.348701\n1852590201␟48.8276523␟2.3494784\n3761637496␟48.8278544␟2.3473522\n1829061602␟48.8275089␟2.3484223\n2576426850-2576426853␟48.827429699999996␟2.34868405\n1829061614␟48.8273732␟2.3487375\n1829061614␟2576426850-2576426853\n3761637486-3761637489␟92192237\n1829061614␟92192237\n1829061602␟3761637496\n1829061602␟2576426850-2576426853\n2576426850-2576426853␟3761637486-3761637489\n1829061602␟3761637486-3761637489\n1852590201␟92192237", '␟');
-        graph = discretize(graph, 6.0);
+        let mut graph = Graph::_from("92192237␟48.8275872␟2.3490245\n3761637486-3761637489␟48.8275351␟2.348701\n1852590201␟48.8276523␟2.3494784\n3761637496␟48.8278544␟2.3473522\n1829061602␟48.8275089␟2.3484223\n2576426850-2576426853␟48.827429699999996␟2.34868405\n1829061614␟48.8273732␟2.3487375\n1829061614␟2576426850-2576426853\n3761637486-3761637489␟92192237\n1829061614␟92192237\n1829061602␟3761637496\n1829061602␟2576426850-2576426853\n2576426850-2576426853␟3761637486-3761637489\n1829061602␟3761637486-3761637489\n1852590201␟92192237", '␟')
+        .unwrap();
+        graph = discretize(graph, 6.0).unwrap();
         assert!(graph.nodes.len() == 35);
         assert!(graph.links.len() / 2 == 36);
         let expected: Vec<(&str, usize)> = vec![
@@ -99,7 +108,16 @@ mod tests {
         ];
         for (node, degree) in expected {
             assert!(graph.nodes.contains_key(node));
-            assert!(graph.get_node(node).neighbours.len() == degree);
+            assert!(graph.get_node(node).unwrap().neighbours.len() == degree);
         }
     }
+
+    #[test]
+    fn test_discretize_shared_edge_id() {
+        let graph = Graph::_from("u␟48.8275872␟2.3490245\nv␟48.8278544␟2.3473522\nw␟48.8275089␟2.3484223\nu␟v␟x␟300\nv␟w␟x␟300", '␟').unwrap();
+        assert!(matches!(
+            discretize(graph, 100.0),
+            Err(GraphError::InvalidRecord(_))
+        ));
+    }
 }
@@ -0,0 +1,77 @@
+use crate::{Graph, GraphError, Order};
+use std::io::Write;
+
+/// Meters by degree of latitude, positions are written in meters from the south west corner
+const METERS_BY_DEGREE: f64 = 111_320.0;
+
+/// Writes an undirected Graphviz graph, nodes are pinned at their position (pos) so that neato
+/// -n draws the map, edges hold their id and length
+pub fn write_dot(graph: &Graph, writer: &mut impl Write, order: Order) -> Result<(), GraphError> {
+    let nodes = graph.sorted_nodes(order);
+    let points = nodes
+        .iter()
+        .map(|node| node.point())
+        .collect::<Result<Vec<_>, _>>()?;
+    let west = points
+        .iter()
+        .map(|point| point.x)
+        .fold(f64::INFINITY, f64::min);
+    let south = points
+        .iter()
+        .map(|point| point.y)
+        .fold(f64::INFINITY, f64::min);
+    let north = points
+        .iter()
+        .map(|point| point.y)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let ratio = ((south + north) / 2.0).to_radians().cos();
+    writeln!(writer, "graph ophois {{")?;
+    for line in &graph.header {
+        writeln!(writer, "  // {}", line)?;
+    }
+    for (node, point) in nodes.iter().zip(&points) {
+        writeln!(
+            writer,
+            "  {} [pos=\"{:.2},{:.2}!\"];",
+            quote(&node.id),
+            (point.x - west) * ratio * METERS_BY_DEGREE,
+            (point.y - south) * METERS_BY_DEGREE
+        )?;
+    }
+    for (source, target) in graph.sorted_pairs(order) {
+        for edge in graph.sorted_edges(source, target, order) {
+            writeln!(
+                writer,
+                "  {} -- {} [id={}, length={}];",
+                quote(source),
+                quote(target),
+                quote(&edge.id),
+                edge.length
+            )?;
+        }
+    }
+    writeln!(writer, "}}")?;
+    writer.flush()?;
+    return Ok(());
+}
+
+fn quote(id: &str) -> String {
+    return format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_write_dot() {
+        let graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637489␟3761637488␟3761637488-3761637489␟2.5\n3761637489␟3761637488␟\"quoted\"␟3", '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_dot(&graph, &mut output, Order::Natural).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("graph ophois {\n  \"3761637488\" [pos=\"0.00,0.00!\"];\n"));
+        assert!(output.contains("\"3761637489\" [pos=\"2.18,0.41!\"];"));
+        assert!(output
+            .contains("  \"3761637488\" -- \"3761637489\" [id=\"\\\"quoted\\\"\", length=3];"));
+        assert!(output.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,41 @@
+use std::fmt;
+use std::io;
+
+/// Errors raised while loading or editing a graph
+#[derive(Debug)]
+pub enum GraphError {
+    Io(io::Error),
+    InvalidRecord(String),
+    NodeNotFound(String),
+    LinkNotFound(String, String),
+    /// Wraps any error raised while loading a record with its line number (starting at 1) and content
+    Line {
+        number: usize,
+        content: String,
+        error: Box<GraphError>,
+    },
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GraphError::Io(error) => write!(f, "{}", error),
+            GraphError::InvalidRecord(reason) => write!(f, "{}", reason),
+            GraphError::NodeNotFound(node_id) => write!(f, "node {} not found", node_id),
+            GraphError::LinkNotFound(u, v) => write!(f, "link {} {} not found", u, v),
+            GraphError::Line {
+                number,
+                content,
+                error,
+            } => write!(f, "line {}: {}\n{}", number, error, content),
+        }
+    }
+}
+
+impl std::error::Error for GraphError {}
+
+impl From<io::Error> for GraphError {
+    fn from(error: io::Error) -> GraphError {
+        return GraphError::Io(error);
+    }
+}
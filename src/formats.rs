@@ -0,0 +1,273 @@
+use crate::{
+    read_binary, read_geojson, read_graphml, write_binary, write_dot, write_geojson, write_gpx,
+    write_graphml, write_kml, write_matrix_market, write_metis, write_osm, Graph, GraphError,
+    GraphFormat, Order,
+};
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+/// Builds a graph from a buffered input
+pub trait GraphReader {
+    fn read(&self, reader: &mut dyn BufRead) -> Result<Graph, GraphError>;
+}
+
+/// Encodes a graph to an output, flushed once written
+pub trait GraphWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError>;
+}
+
+/// File extensions each format is recognized by
+const EXTENSIONS: [(&str, GraphFormat); 13] = [
+    ("graph", GraphFormat::Text),
+    ("txt", GraphFormat::Text),
+    ("bin", GraphFormat::Binary),
+    ("geojson", GraphFormat::GeoJson),
+    ("json", GraphFormat::GeoJson),
+    ("graphml", GraphFormat::GraphMl),
+    ("osm", GraphFormat::Osm),
+    ("dot", GraphFormat::Dot),
+    ("gv", GraphFormat::Dot),
+    ("metis", GraphFormat::Metis),
+    ("mtx", GraphFormat::MatrixMarket),
+    ("gpx", GraphFormat::Gpx),
+    ("kml", GraphFormat::Kml),
+];
+
+pub struct TextReader {
+    pub separator: char,
+}
+
+pub struct BinaryReader;
+
+pub struct GeoJsonReader;
+
+pub struct GraphMlReader;
+
+pub struct TextWriter {
+    pub separator: char,
+    pub order: Order,
+}
+
+pub struct BinaryWriter {
+    pub order: Order,
+}
+
+pub struct GeoJsonWriter {
+    pub order: Order,
+}
+
+pub struct GraphMlWriter {
+    pub order: Order,
+}
+
+pub struct OsmWriter {
+    pub order: Order,
+}
+
+pub struct DotWriter {
+    pub order: Order,
+}
+
+pub struct MetisWriter {
+    pub order: Order,
+    pub weighted: bool,
+}
+
+pub struct MatrixMarketWriter {
+    pub order: Order,
+}
+
+pub struct GpxWriter {
+    pub order: Order,
+}
+
+pub struct KmlWriter {
+    pub order: Order,
+}
+
+impl GraphReader for TextReader {
+    fn read(&self, reader: &mut dyn BufRead) -> Result<Graph, GraphError> {
+        return Graph::read(reader, self.separator);
+    }
+}
+
+impl GraphWriter for TextWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        graph.write(&mut &mut *writer, self.separator, self.order)?;
+        return Ok(());
+    }
+}
+
+impl GraphReader for BinaryReader {
+    fn read(&self, reader: &mut dyn BufRead) -> Result<Graph, GraphError> {
+        return read_binary(&mut &mut *reader);
+    }
+}
+
+impl GraphWriter for BinaryWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_binary(graph, &mut &mut *writer, self.order);
+    }
+}
+
+impl GraphReader for GeoJsonReader {
+    fn read(&self, reader: &mut dyn BufRead) -> Result<Graph, GraphError> {
+        return read_geojson(&mut &mut *reader);
+    }
+}
+
+impl GraphWriter for GeoJsonWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_geojson(graph, &mut &mut *writer, self.order);
+    }
+}
+
+impl GraphReader for GraphMlReader {
+    fn read(&self, reader: &mut dyn BufRead) -> Result<Graph, GraphError> {
+        return read_graphml(reader);
+    }
+}
+
+impl GraphWriter for GraphMlWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_graphml(graph, &mut &mut *writer, self.order);
+    }
+}
+
+impl GraphWriter for OsmWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_osm(graph, &mut &mut *writer, self.order);
+    }
+}
+
+impl GraphWriter for DotWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_dot(graph, &mut &mut *writer, self.order);
+    }
+}
+
+impl GraphWriter for MetisWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_metis(graph, &mut &mut *writer, self.order, self.weighted);
+    }
+}
+
+impl GraphWriter for MatrixMarketWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_matrix_market(graph, &mut &mut *writer, self.order);
+    }
+}
+
+impl GraphWriter for GpxWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_gpx(graph, &mut &mut *writer, self.order);
+    }
+}
+
+impl GraphWriter for KmlWriter {
+    fn write(&self, graph: &Graph, writer: &mut dyn Write) -> Result<(), GraphError> {
+        return write_kml(graph, &mut &mut *writer, self.order);
+    }
+}
+
+impl GraphFormat {
+    /// Format of a file by its extension, if known
+    pub fn from_path(path: &str) -> Option<GraphFormat> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+        return EXTENSIONS
+            .iter()
+            .find(|(known, _)| *known == extension)
+            .map(|(_, format)| *format);
+    }
+
+    /// Reader of the format, only text, bin, geojson and graphml graphs are read
+    pub fn reader(self, separator: char) -> Result<Box<dyn GraphReader>, GraphError> {
+        return match self {
+            GraphFormat::Text => Ok(Box::new(TextReader { separator })),
+            GraphFormat::Binary => Ok(Box::new(BinaryReader)),
+            GraphFormat::GeoJson => Ok(Box::new(GeoJsonReader)),
+            GraphFormat::GraphMl => Ok(Box::new(GraphMlReader)),
+            format => Err(GraphError::InvalidRecord(format!(
+                "{} graphs are only written, expected text, bin, geojson or graphml input",
+                format
+            ))),
+        };
+    }
+
+    /// Writer of the format, the separator only matters to the text format
+    pub fn writer(self, separator: char, order: Order) -> Box<dyn GraphWriter> {
+        return match self {
+            GraphFormat::Text => Box::new(TextWriter { separator, order }),
+            GraphFormat::Binary => Box::new(BinaryWriter { order }),
+            GraphFormat::GeoJson => Box::new(GeoJsonWriter { order }),
+            GraphFormat::GraphMl => Box::new(GraphMlWriter { order }),
+            GraphFormat::Osm => Box::new(OsmWriter { order }),
+            GraphFormat::Dot => Box::new(DotWriter { order }),
+            GraphFormat::Metis => Box::new(MetisWriter {
+                order,
+                weighted: false,
+            }),
+            GraphFormat::WeightedMetis => Box::new(MetisWriter {
+                order,
+                weighted: true,
+            }),
+            GraphFormat::MatrixMarket => Box::new(MatrixMarketWriter { order }),
+            GraphFormat::Gpx => Box::new(GpxWriter { order }),
+            GraphFormat::Kml => Box::new(KmlWriter { order }),
+        };
+    }
+}
+
+/// Reads a graph in the given format from any input, buffered
+pub fn read_graph(
+    input: impl Read,
+    format: GraphFormat,
+    separator: char,
+) -> Result<Graph, GraphError> {
+    return format
+        .reader(separator)?
+        .read(&mut io::BufReader::new(input));
+}
+
+/// Writes a graph in the given format to any output, buffered
+pub fn write_graph(
+    graph: &Graph,
+    output: impl Write,
+    format: GraphFormat,
+    separator: char,
+    order: Order,
+) -> Result<(), GraphError> {
+    return format
+        .writer(separator, order)
+        .write(graph, &mut io::BufWriter::new(output));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_formats() {
+        assert_eq!(
+            GraphFormat::from_path("Pantin.graphml"),
+            Some(GraphFormat::GraphMl)
+        );
+        assert_eq!(
+            GraphFormat::from_path("data/Pantin.BIN"),
+            Some(GraphFormat::Binary)
+        );
+        assert_eq!(GraphFormat::from_path("Pantin"), None);
+        assert_eq!(GraphFormat::from_path("Pantin.csv"), None);
+        let graph = Graph::_from("# ophois 0.3.0 extract separator='␟'\n3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637489␟3761637488␟3761637488-3761637489␟2.5", '␟').unwrap();
+        let mut expected: Vec<u8> = Vec::new();
+        graph.write(&mut expected, ' ', Order::Natural).unwrap();
+        for format in [GraphFormat::Text, GraphFormat::Binary, GraphFormat::GraphMl] {
+            let mut output: Vec<u8> = Vec::new();
+            write_graph(&graph, &mut output, format, ' ', Order::Natural).unwrap();
+            let read = read_graph(output.as_slice(), format, ' ').unwrap();
+            let mut actual: Vec<u8> = Vec::new();
+            write_graph(&read, &mut actual, GraphFormat::Text, ' ', Order::Natural).unwrap();
+            assert_eq!(actual, expected, "{} round trip", format);
+        }
+        assert!(read_graph("".as_bytes(), GraphFormat::Kml, ' ').is_err());
+    }
+}
@@ -1,9 +1,11 @@
+/// Geographic point, x is the longitude and y the latitude in degrees
 #[derive(Debug, PartialEq)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
 }
 
+/// Great-circle distance in meters
 pub fn haversine_distance(a: &Point, b: &Point) -> f64 {
     let r: f64 = 6371008.7714; // IUGG  mean earth radius
     let d_lat: f64 = (b.y - a.y).to_radians();
@@ -23,6 +25,7 @@ pub fn midpoint(a: &Point, b: &Point) -> Point {
     };
 }
 
+/// Point at a fraction (between 0 and 1) of the segment from a to b
 pub fn get_point_from_line(a: &Point, b: &Point, part: f64) -> Point {
     return Point {
         x: a.x + (part * (b.x - a.x)),
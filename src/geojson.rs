@@ -0,0 +1,193 @@
+use crate::{Edge, Graph, GraphError, Node, Order};
+use serde_json::{json, Value};
+use std::io::{Read, Write};
+
+/// Writes a FeatureCollection feature by feature: a Point by node with its id and degree, then a
+/// LineString by edge with its id, nodes and length
+pub fn write_geojson(
+    graph: &Graph,
+    writer: &mut impl Write,
+    order: Order,
+) -> Result<(), GraphError> {
+    write!(writer, "{{\"type\":\"FeatureCollection\",\"features\":[")?;
+    let mut first = true;
+    for node in graph.sorted_nodes(order) {
+        let feature = json!({
+            "type": "Feature",
+            "geometry": {"type": "Point", "coordinates": position(node)?},
+            "properties": {"id": node.id, "degree": node.neighbours.len()},
+        });
+        write_feature(writer, &feature, &mut first)?;
+    }
+    for (source, target) in graph.sorted_pairs(order) {
+        let coordinates = [
+            position(graph.get_node(source)?)?,
+            position(graph.get_node(target)?)?,
+        ];
+        for edge in graph.sorted_edges(source, target, order) {
+            let feature = json!({
+                "type": "Feature",
+                "geometry": {"type": "LineString", "coordinates": coordinates},
+                "properties": {
+                    "id": edge.id,
+                    "source": source,
+                    "target": target,
+                    "length": edge.length,
+                },
+            });
+            write_feature(writer, &feature, &mut first)?;
+        }
+    }
+    writeln!(writer, "\n]}}")?;
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Reads a FeatureCollection written by write_geojson: Points with an id are nodes and LineStrings
+/// with a source and a target are edges, their id and length default to the link id and the
+/// distance between their nodes. Other features are ignored, a repeated node id must keep its coordinates
+pub fn read_geojson(reader: &mut impl Read) -> Result<Graph, GraphError> {
+    let mut input = String::new();
+    reader.read_to_string(&mut input)?;
+    let collection: Value = serde_json::from_str(&input)
+        .map_err(|error| GraphError::InvalidRecord(format!("invalid GeoJSON {}", error)))?;
+    let features = collection["features"].as_array().ok_or_else(|| {
+        GraphError::InvalidRecord("expected a GeoJSON FeatureCollection".to_string())
+    })?;
+    let mut graph = Graph::new();
+    for feature in features {
+        if let ("Point", Some(id)) = (
+            feature["geometry"]["type"].as_str().unwrap_or_default(),
+            property(feature, "id"),
+        ) {
+            let coordinates = &feature["geometry"]["coordinates"];
+            match (coordinates[0].as_f64(), coordinates[1].as_f64()) {
+                (Some(longitude), Some(latitude)) => graph.insert_checked_node(Node {
+                    id,
+                    latitude: latitude.to_string(),
+                    longitude: longitude.to_string(),
+                    neighbours: Vec::new(),
+                })?,
+                _ => {
+                    return Err(GraphError::InvalidRecord(format!(
+                        "node {} has invalid coordinates {}",
+                        id, coordinates
+                    )))
+                }
+            }
+        }
+    }
+    for feature in features {
+        if let ("LineString", Some(source), Some(target)) = (
+            feature["geometry"]["type"].as_str().unwrap_or_default(),
+            property(feature, "source"),
+            property(feature, "target"),
+        ) {
+            let edge = Edge {
+                id: property(feature, "id").unwrap_or_else(|| graph.link_id(&source, &target)),
+                length: match feature["properties"]["length"].as_f64() {
+                    Some(length) => length,
+                    None => graph.distance(&source, &target)?,
+                },
+            };
+            graph.insert_edge(&source, &target, edge)?;
+        }
+    }
+    return Ok(graph);
+}
+
+/// String or number property of a feature
+fn property(feature: &Value, name: &str) -> Option<String> {
+    return match &feature["properties"][name] {
+        Value::String(value) => Some(value.clone()),
+        Value::Number(value) => Some(value.to_string()),
+        _ => None,
+    };
+}
+
+fn write_feature(
+    writer: &mut impl Write,
+    feature: &serde_json::Value,
+    first: &mut bool,
+) -> Result<(), GraphError> {
+    if !*first {
+        write!(writer, ",")?;
+    }
+    *first = false;
+    write!(writer, "\n{}", feature)?;
+    return Ok(());
+}
+
+/// GeoJSON positions are longitude then latitude
+fn position(node: &Node) -> Result<[f64; 2], GraphError> {
+    let mut position = [0.0; 2];
+    for (index, coordinate) in [&node.longitude, &node.latitude].into_iter().enumerate() {
+        position[index] = coordinate
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .ok_or_else(|| {
+                GraphError::InvalidRecord(format!(
+                    "node {} has invalid coordinate {}",
+                    node.id, coordinate
+                ))
+            })?;
+    }
+    return Ok(position);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_write_geojson() {
+        let graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n2268836829␟48.8276001␟2.3486802\n3761637489␟3761637488\n3761637489␟2268836829␟2268836829-3761637489␟5.5\n3761637489␟2268836829␟2268836829-3761637489~1␟12.25", '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_geojson(&graph, &mut output, Order::Natural).unwrap();
+        let collection: Value = serde_json::from_slice(&output).unwrap();
+        let features = collection["features"].as_array().unwrap();
+        assert_eq!(features.len(), 6);
+        assert_eq!(features[0]["properties"]["id"], "2268836829");
+        assert_eq!(features[2]["properties"]["degree"], 2);
+        assert_eq!(
+            features[0]["geometry"]["coordinates"],
+            json!([2.3486802, 48.8276001])
+        );
+        assert_eq!(features[3]["geometry"]["type"], "LineString");
+        assert_eq!(features[4]["properties"]["id"], "2268836829-3761637489~1");
+        assert_eq!(features[4]["properties"]["length"], 12.25);
+        let mut output: Vec<u8> = Vec::new();
+        write_geojson(&Graph::new(), &mut output, Order::Natural).unwrap();
+        let collection: Value = serde_json::from_slice(&output).unwrap();
+        assert!(collection["features"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_read_geojson() {
+        let graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n2268836829␟48.8276001␟2.3486802\n3761637489␟3761637488\n3761637489␟2268836829␟2268836829-3761637489␟5.5\n3761637489␟2268836829␟2268836829-3761637489~1␟12.25", '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_geojson(&graph, &mut output, Order::Natural).unwrap();
+        let read = read_geojson(&mut output.as_slice()).unwrap();
+        let mut expected: Vec<u8> = Vec::new();
+        graph.write(&mut expected, '␟', Order::Natural).unwrap();
+        let mut actual: Vec<u8> = Vec::new();
+        read.write(&mut actual, '␟', Order::Natural).unwrap();
+        assert_eq!(actual, expected);
+        let collection = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [2.3486683, 48.8275416]}, "properties": {"id": 1}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [2.348698, 48.8275453]}, "properties": {"id": 2}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": []}, "properties": {"source": 1, "target": 2}}
+        ]}"#;
+        let read = read_geojson(&mut collection.as_bytes()).unwrap();
+        assert_eq!(read.get_edges("1", "2")[0].id, "1-2");
+        assert!(read_geojson(&mut "{\"type\": \"Point\"}".as_bytes()).is_err());
+        let collection = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [2.3486683, 48.8275416]}, "properties": {"id": 1}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [2.348698, 48.8275453]}, "properties": {"id": 1}}
+        ]}"#;
+        assert!(matches!(
+            read_geojson(&mut collection.as_bytes()),
+            Err(GraphError::InvalidRecord(_))
+        ));
+    }
+}
@@ -0,0 +1,169 @@
+use crate::{escape_xml, Graph, GraphError, Node, Order};
+use std::collections::HashSet;
+use std::io::Write;
+
+/// Splits the graph into chains of links: maximal paths whose inner nodes have degree two,
+/// cycles of degree two nodes start from their first node in the given order
+pub fn chains(graph: &Graph, order: Order) -> Vec<Vec<&str>> {
+    let nodes = graph.sorted_nodes(order);
+    let mut visited: HashSet<(&str, &str)> = HashSet::new();
+    let mut chains: Vec<Vec<&str>> = Vec::new();
+    // junctions and dead ends first, so that chains only meet at their ends
+    let starts = nodes
+        .iter()
+        .filter(|node| node.neighbours.len() != 2)
+        .chain(nodes.iter().filter(|node| node.neighbours.len() == 2));
+    for start in starts {
+        for first in &start.neighbours {
+            if visited.contains(&pair(&start.id, first)) {
+                continue;
+            }
+            let mut chain: Vec<&str> = vec![&start.id];
+            let (mut previous, mut current): (&str, &str) = (&start.id, first);
+            loop {
+                visited.insert(pair(previous, current));
+                chain.push(current);
+                let node = &graph.nodes[current];
+                if node.neighbours.len() != 2 || current == start.id {
+                    break;
+                }
+                let next = node
+                    .neighbours
+                    .iter()
+                    .find(|neighbour| *neighbour != previous)
+                    .unwrap_or(&node.neighbours[0]);
+                if visited.contains(&pair(current, next)) {
+                    break;
+                }
+                previous = current;
+                current = next;
+            }
+            chains.push(chain);
+        }
+    }
+    return chains;
+}
+
+/// Writes a GPX file with a track by chain of links, named after the ids of its end nodes
+pub fn write_gpx(graph: &Graph, writer: &mut impl Write, order: Order) -> Result<(), GraphError> {
+    write_start(writer, &graph.header)?;
+    for chain in chains(graph, order) {
+        let name = format!("{} {}", chain[0], chain[chain.len() - 1]);
+        let nodes: Vec<&Node> = chain.iter().map(|node_id| &graph.nodes[*node_id]).collect();
+        write_track(writer, &name, &nodes)?;
+    }
+    writeln!(writer, "</gpx>")?;
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Writes a GPX file with a single track following the route, a sequence of linked node ids
+pub fn write_gpx_route(
+    graph: &Graph,
+    route: &[&str],
+    writer: &mut impl Write,
+) -> Result<(), GraphError> {
+    let nodes = route_nodes(graph, route)?;
+    write_start(writer, &graph.header)?;
+    write_track(writer, "route", &nodes)?;
+    writeln!(writer, "</gpx>")?;
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Nodes of a route, fails if two consecutive nodes are not linked
+pub(crate) fn route_nodes<'a>(
+    graph: &'a Graph,
+    route: &[&str],
+) -> Result<Vec<&'a Node>, GraphError> {
+    for step in route.windows(2) {
+        if graph.get_edges(step[0], step[1]).is_empty() {
+            return Err(GraphError::LinkNotFound(
+                step[0].to_string(),
+                step[1].to_string(),
+            ));
+        }
+    }
+    return route
+        .iter()
+        .map(|node_id| graph.get_node(node_id))
+        .collect();
+}
+
+fn pair<'a>(u: &'a str, v: &'a str) -> (&'a str, &'a str) {
+    return if u < v { (u, v) } else { (v, u) };
+}
+
+fn write_start(writer: &mut impl Write, header: &[String]) -> Result<(), GraphError> {
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        writer,
+        "<gpx version=\"1.1\" creator=\"ophois {}\" xmlns=\"http://www.topografix.com/GPX/1/1\">",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    if !header.is_empty() {
+        writeln!(
+            writer,
+            "  <metadata><desc>{}</desc></metadata>",
+            escape_xml(&header.join("\n"))
+        )?;
+    }
+    return Ok(());
+}
+
+fn write_track(writer: &mut impl Write, name: &str, nodes: &[&Node]) -> Result<(), GraphError> {
+    writeln!(writer, "  <trk>")?;
+    writeln!(writer, "    <name>{}</name>", escape_xml(name))?;
+    writeln!(writer, "    <trkseg>")?;
+    for node in nodes {
+        writeln!(
+            writer,
+            "      <trkpt lat=\"{}\" lon=\"{}\"><name>{}</name></trkpt>",
+            escape_xml(&node.latitude),
+            escape_xml(&node.longitude),
+            escape_xml(&node.id)
+        )?;
+    }
+    writeln!(writer, "    </trkseg>")?;
+    writeln!(writer, "  </trk>")?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const GRAPH: &str = "1␟48.8275185␟2.3484976\n2␟48.8275416␟2.3486683\n3␟48.8275453␟2.348698\n4␟48.8275249␟2.348704\n5␟48.8276001␟2.3486802\n6␟48.8278544␟2.3473522\n7␟48.8278001␟2.3472001\n8␟48.8277001␟2.3473001\n1␟2\n2␟3\n3␟4\n3␟5\n6␟7\n7␟8\n8␟6";
+
+    #[test]
+    fn test_chains() {
+        let graph = Graph::_from(GRAPH, '␟').unwrap();
+        assert_eq!(
+            chains(&graph, Order::Natural),
+            vec![
+                vec!["1", "2", "3"],
+                vec!["3", "4"],
+                vec!["3", "5"],
+                vec!["6", "7", "8", "6"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_gpx() {
+        let graph = Graph::_from(GRAPH, '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_gpx(&graph, &mut output, Order::Natural).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("<trk>").count(), 4);
+        assert!(output.contains("<name>1 3</name>\n    <trkseg>\n      <trkpt lat=\"48.8275185\" lon=\"2.3484976\"><name>1</name></trkpt>\n"));
+        assert!(output.ends_with("</gpx>\n"));
+        let mut output: Vec<u8> = Vec::new();
+        write_gpx_route(&graph, &["1", "2", "3", "5"], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("<trkpt ").count(), 4);
+        assert!(matches!(
+            write_gpx_route(&graph, &["1", "3"], &mut Vec::new()),
+            Err(GraphError::LinkNotFound(_, _))
+        ));
+    }
+}
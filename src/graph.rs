@@ -1,10 +1,16 @@
-use crate::determinist;
-use crate::Point;
+use crate::{
+    check_separator, determinist, haversine_distance, join_record, natural_cmp, provenance,
+    split_record, GraphError, Origin, Point,
+};
 use std::collections::{HashMap, HashSet};
-use std::io::{self, BufRead};
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
 
+/// Directed link from a source node id to a target node id
 pub type Link = (String, String);
 
+/// Intersection or point of a street, coordinates are kept as written in the input
 #[derive(Clone, Eq, Hash, PartialEq, Debug)]
 pub struct Node {
     pub id: String,
@@ -16,25 +22,131 @@ pub struct Node {
 impl Node {
     pub fn _new(id: String) -> Node {
         return Node {
-            id: id,
+            id,
             latitude: "".to_string(),
             longitude: "".to_string(),
             neighbours: Vec::new(),
         };
     }
 
-    pub fn point(&self) -> Point {
-        return Point {
-            x: self.longitude.parse().unwrap(),
-            y: self.latitude.parse().unwrap(),
+    /// Fails on coordinates which are not numbers, as fields are public
+    pub fn point(&self) -> Result<Point, GraphError> {
+        return match (self.longitude.parse(), self.latitude.parse()) {
+            (Ok(x), Ok(y)) => Ok(Point { x, y }),
+            _ => Err(GraphError::InvalidRecord(format!(
+                "node {} has invalid coordinates {} {}",
+                self.id, self.latitude, self.longitude
+            ))),
         };
     }
 }
 
+/// A street between two nodes, several edges may join the same pair of nodes
+#[derive(Clone, PartialEq, Debug)]
+pub struct Edge {
+    pub id: String,
+    /// Length is expressed in meters
+    pub length: f64,
+}
+
+/// Order in which nodes and links are listed
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Order {
+    /// Ids are compared chunk by chunk, digits by their numeric value
+    Natural,
+    Insertion,
+}
+
+impl FromStr for Order {
+    type Err = String;
+
+    fn from_str(order: &str) -> Result<Order, String> {
+        return match order {
+            "natural" => Ok(Order::Natural),
+            "insertion" => Ok(Order::Insertion),
+            _ => Err(format!(
+                "unknown order {}, expected natural or insertion",
+                order
+            )),
+        };
+    }
+}
+
+/// Encoding of a graph file, text is the separated values format and bin its binary counterpart.
+/// Text, bin, geojson and graphml graphs are read and written, the other formats are only written
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GraphFormat {
+    Text,
+    Binary,
+    GeoJson,
+    GraphMl,
+    Osm,
+    Dot,
+    Metis,
+    WeightedMetis,
+    MatrixMarket,
+    Gpx,
+    Kml,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<GraphFormat, String> {
+        return match format {
+            "text" => Ok(GraphFormat::Text),
+            "bin" => Ok(GraphFormat::Binary),
+            "geojson" => Ok(GraphFormat::GeoJson),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            "osm" => Ok(GraphFormat::Osm),
+            "dot" => Ok(GraphFormat::Dot),
+            "metis" => Ok(GraphFormat::Metis),
+            "metis-weighted" => Ok(GraphFormat::WeightedMetis),
+            "mtx" => Ok(GraphFormat::MatrixMarket),
+            "gpx" => Ok(GraphFormat::Gpx),
+            "kml" => Ok(GraphFormat::Kml),
+            _ => Err(format!(
+                "unknown format {}, expected text, bin, geojson, graphml, osm, dot, metis, metis-weighted, mtx, gpx or kml",
+                format
+            )),
+        };
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            GraphFormat::Text => "text",
+            GraphFormat::Binary => "bin",
+            GraphFormat::GeoJson => "geojson",
+            GraphFormat::GraphMl => "graphml",
+            GraphFormat::Osm => "osm",
+            GraphFormat::Dot => "dot",
+            GraphFormat::Metis => "metis",
+            GraphFormat::WeightedMetis => "metis-weighted",
+            GraphFormat::MatrixMarket => "mtx",
+            GraphFormat::Gpx => "gpx",
+            GraphFormat::Kml => "kml",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Undirected streets graph, every link is stored in both directions
 #[derive(Debug)]
 pub struct Graph {
     pub nodes: HashMap<String, Node>,
     pub links: HashMap<(String, String), usize>,
+    /// Parallel edges of each link, keyed by the determinist pair of its nodes
+    pub edges: HashMap<(String, String), Vec<Edge>>,
+    /// Insertion ranks of nodes and links, parallel edges keep their own order
+    pub node_ranks: HashMap<String, usize>,
+    pub link_ranks: HashMap<(String, String), usize>,
+    pub next_rank: usize,
+    /// Commented lines (starting with #) of a graph file, such as the commands which produced it
+    pub header: Vec<String>,
+    /// Original nodes of the nodes created by ophois, see origins
+    pub origins: HashMap<String, Origin>,
 }
 
 impl Graph {
@@ -42,121 +154,265 @@ impl Graph {
         return Graph {
             nodes: HashMap::new(),
             links: HashMap::new(),
+            edges: HashMap::new(),
+            node_ranks: HashMap::new(),
+            link_ranks: HashMap::new(),
+            next_rank: 0,
+            header: Vec::new(),
+            origins: HashMap::new(),
         };
     }
 
-    pub fn _from(input: &str, separator: char) -> Graph {
+    /// Parses a graph held in a string, mostly to write tests
+    pub fn _from(input: &str, separator: char) -> Result<Graph, GraphError> {
+        return Graph::read(input.as_bytes(), separator);
+    }
+
+    /// Reads a graph from the standard input
+    pub fn load(separator: char) -> Result<Graph, GraphError> {
+        return Graph::read(io::stdin().lock(), separator);
+    }
+
+    /// Reads a graph in text format, errors tell the line number and content of the faulty record
+    pub fn read(input: impl BufRead, separator: char) -> Result<Graph, GraphError> {
+        check_separator(separator)?;
         let mut graph = Graph::new();
-        for line in input.lines() {
-            let data: Vec<&str> = line.split(separator).collect();
-            match data.len() {
-                3 => {
-                    graph.insert_node(Node {
-                        id: data[0].to_string(),
-                        latitude: data[1].to_string(),
-                        longitude: data[2].to_string(),
-                        neighbours: Vec::new(),
-                    });
-                }
-                2 => {
-                    graph.insert_link((data[0].to_string(), data[1].to_string()));
-                    graph.insert_link((data[1].to_string(), data[0].to_string()));
-                }
-                _ => panic!(
-                    "Wrong input length -> {}!\nnode=id\nlink=node_id node_id",
-                    data.len()
-                ),
-            }
+        for (index, line) in input.lines().enumerate() {
+            graph.insert_line(index + 1, &line?, separator)?;
         }
-        return graph;
+        return Ok(graph);
     }
 
-    pub fn load(separator: char) -> Graph {
+    /// Reads every valid record of a graph in text format and returns the faulty ones apart
+    pub fn read_lenient(
+        input: impl BufRead,
+        separator: char,
+    ) -> Result<(Graph, Vec<GraphError>), GraphError> {
+        check_separator(separator)?;
         let mut graph = Graph::new();
-        let input = io::stdin();
-        for line in input.lock().lines() {
-            let line = line.unwrap();
-            let data: Vec<&str> = line.split(separator).collect();
-            match data.len() {
-                3 => {
-                    if !graph.nodes.contains_key(data[0]) {
-                        graph.insert_node(Node {
-                            id: data[0].to_string(),
-                            latitude: data[1].to_string(),
-                            longitude: data[2].to_string(),
-                            neighbours: Vec::new(),
-                        });
-                    }
+        let mut errors: Vec<GraphError> = Vec::new();
+        for (index, line) in input.lines().enumerate() {
+            if let Err(error) = graph.insert_line(index + 1, &line?, separator) {
+                errors.push(error);
+            }
+        }
+        return Ok((graph, errors));
+    }
+
+    fn insert_line(
+        &mut self,
+        number: usize,
+        line: &str,
+        separator: char,
+    ) -> Result<(), GraphError> {
+        if let Some(comment) = line.strip_prefix('#') {
+            self.header.push(comment.trim().to_string());
+            return Ok(());
+        }
+        return self
+            .insert_record(line, separator)
+            .map_err(|error| GraphError::Line {
+                number,
+                content: line.to_string(),
+                error: Box::new(error),
+            });
+    }
+
+    fn insert_record(&mut self, line: &str, separator: char) -> Result<(), GraphError> {
+        let fields = split_record(line, separator)?;
+        let data: Vec<&str> = fields.iter().map(|field| field.as_str()).collect();
+        match data.len() {
+            3 => {
+                self.insert_checked_node(Node {
+                    id: data[0].to_string(),
+                    latitude: data[1].to_string(),
+                    longitude: data[2].to_string(),
+                    neighbours: Vec::new(),
+                })?;
+            }
+            2 => {
+                if !self.contains_link((data[0], data[1])) {
+                    let edge = Edge {
+                        id: self.link_id(data[0], data[1]),
+                        length: self.distance(data[0], data[1])?,
+                    };
+                    self.insert_edge(data[0], data[1], edge)?;
                 }
-                2 => {
-                    let (u, v) = (data[0].to_string(), data[1].to_string());
-                    if !graph.contains_link((&u, &v)) && !graph.contains_link((&v, &u)) {
-                        graph.insert_link((u.clone(), v.clone()));
-                        graph.insert_link((v, u));
-                    }
+            }
+            4 => {
+                let length = data[3].parse().map_err(|_| {
+                    GraphError::InvalidRecord(format!("invalid length {}", data[3]))
+                })?;
+                let exists = self
+                    .get_edges(data[0], data[1])
+                    .iter()
+                    .any(|edge| edge.id == data[2]);
+                if !exists {
+                    let edge = Edge {
+                        id: data[2].to_string(),
+                        length,
+                    };
+                    self.insert_edge(data[0], data[1], edge)?;
                 }
-                _ => panic!("Wrong input length!\nnode=id\nlink=node_id node_id"),
+            }
+            _ => {
+                return Err(GraphError::InvalidRecord(format!(
+                    "wrong input length {}, expected node=id latitude longitude or link=node_id node_id [link_id length]",
+                    data.len()
+                )))
             }
         }
-        return graph;
+        return Ok(());
     }
 
-    pub fn show(&self, separator: char) {
-        for (_, node) in self.nodes.iter() {
-            println!(
-                "{}{}{}{}{}",
-                node.id, separator, node.latitude, separator, node.longitude
-            )
+    /// Writes the graph to the standard output, failing if it is closed
+    pub fn show(&self, separator: char, order: Order) -> io::Result<()> {
+        let mut output = io::BufWriter::new(io::stdout().lock());
+        return self.write(&mut output, separator, order);
+    }
+
+    /// Writes the header, nodes then links in text format, fields are escaped (see escape_field)
+    pub fn write(&self, output: &mut impl Write, separator: char, order: Order) -> io::Result<()> {
+        check_separator(separator)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error.to_string()))?;
+        for line in &self.header {
+            writeln!(output, "# {}", line)?;
         }
-        let mut printed: HashSet<(String, String)> = HashSet::new();
-        for ((u, v), _) in self.links.iter() {
-            let (source, target) = determinist(u.clone(), v.clone());
-            if !printed.contains(&(source.clone(), target.clone()))
-                && !printed.contains(&(target.clone(), source.clone()))
-            {
-                printed.insert((source.clone(), target.clone()));
-                println!("{}{}{}", source, separator, target);
+        for node in self.sorted_nodes(order) {
+            let record = [node.id.as_str(), &node.latitude, &node.longitude];
+            writeln!(output, "{}", join_record(&record, separator))?;
+        }
+        for (source, target) in self.sorted_pairs(order) {
+            for edge in self.sorted_edges(source, target, order) {
+                let length = edge.length.to_string();
+                let record = [source.as_str(), target, &edge.id, &length];
+                writeln!(output, "{}", join_record(&record, separator))?;
             }
         }
+        return output.flush();
+    }
+
+    pub fn sorted_nodes(&self, order: Order) -> Vec<&Node> {
+        let mut nodes: Vec<&Node> = self.nodes.values().collect();
+        match order {
+            Order::Natural => nodes.sort_by(|a, b| natural_cmp(&a.id, &b.id)),
+            Order::Insertion => nodes.sort_by(|a, b| {
+                let rank = |node: &Node| *self.node_ranks.get(&node.id).unwrap_or(&usize::MAX);
+                rank(a).cmp(&rank(b)).then(natural_cmp(&a.id, &b.id))
+            }),
+        }
+        return nodes;
+    }
+
+    /// Determinist pairs of linked nodes
+    pub fn sorted_pairs(&self, order: Order) -> Vec<&(String, String)> {
+        let mut pairs: Vec<&(String, String)> = self.edges.keys().collect();
+        let natural = |a: &(String, String), b: &(String, String)| {
+            natural_cmp(&a.0, &b.0).then(natural_cmp(&a.1, &b.1))
+        };
+        match order {
+            Order::Natural => pairs.sort_by(|a, b| natural(a, b)),
+            Order::Insertion => pairs.sort_by(|a, b| {
+                let rank =
+                    |pair: &(String, String)| *self.link_ranks.get(pair).unwrap_or(&usize::MAX);
+                rank(a).cmp(&rank(b)).then(natural(a, b))
+            }),
+        }
+        return pairs;
+    }
+
+    pub fn sorted_edges(&self, u: &str, v: &str, order: Order) -> Vec<&Edge> {
+        let mut edges: Vec<&Edge> = self.get_edges(u, v).iter().collect();
+        if order == Order::Natural {
+            edges.sort_by(|a, b| natural_cmp(&a.id, &b.id));
+        }
+        return edges;
     }
 
     pub fn insert_node(&mut self, node: Node) {
-        self.nodes.entry(node.id.clone()).or_insert(node);
+        if !self.nodes.contains_key(&node.id) {
+            self.node_ranks.insert(node.id.clone(), self.next_rank);
+            self.next_rank += 1;
+            self.nodes.insert(node.id.clone(), node);
+        }
     }
 
-    pub fn remove_node(&mut self, node_id: &str) {
-        let node = self.nodes.get(node_id).unwrap().clone();
-        if node.neighbours.len() > 0 {
-            for neighbour_id in node.neighbours {
-                self.remove_link(&(node_id.to_string(), neighbour_id.clone()));
-                self.remove_link(&(neighbour_id, node_id.to_string()));
+    /// Inserts a node read from an input, a known id must come with the same coordinates
+    pub fn insert_checked_node(&mut self, node: Node) -> Result<(), GraphError> {
+        let mut coordinates: Vec<f64> = Vec::new();
+        for coordinate in [&node.latitude, &node.longitude] {
+            coordinates.push(coordinate.parse().map_err(|_| {
+                GraphError::InvalidRecord(format!("invalid coordinate {}", coordinate))
+            })?);
+        }
+        if let Some(known) = self.nodes.get(&node.id) {
+            if known.latitude.parse::<f64>() != Ok(coordinates[0])
+                || known.longitude.parse::<f64>() != Ok(coordinates[1])
+            {
+                return Err(GraphError::InvalidRecord(format!(
+                    "node {} already has coordinates {} {}",
+                    known.id, known.latitude, known.longitude
+                )));
             }
+            return Ok(());
+        }
+        self.insert_node(node);
+        return Ok(());
+    }
+
+    /// Removes the node along with its links, their edges and its origin
+    pub fn remove_node(&mut self, node_id: &str) -> Result<(), GraphError> {
+        let node = self.get_node(node_id)?.clone();
+        for neighbour_id in node.neighbours {
+            self.remove_link(&(node_id.to_string(), neighbour_id.clone()))?;
+            self.remove_link(&(neighbour_id, node_id.to_string()))?;
         }
         self.nodes.remove(node_id);
+        self.node_ranks.remove(node_id);
+        self.origins.remove(node_id);
+        return Ok(());
     }
 
-    pub fn get_node(&self, node_id: &str) -> &Node {
-        return self.nodes.get(node_id).unwrap();
+    /// Fails with NodeNotFound if the graph does not contain the node
+    pub fn get_node(&self, node_id: &str) -> Result<&Node, GraphError> {
+        return self
+            .nodes
+            .get(node_id)
+            .ok_or_else(|| GraphError::NodeNotFound(node_id.to_string()));
     }
 
     pub fn contains_link(&self, (u, v): (&str, &str)) -> bool {
         return self.links.contains_key(&(u.to_string(), v.to_string()));
     }
 
-    pub fn insert_link(&mut self, link: Link) {
+    /// Adds the target to the neighbours of the source, without any edge nor reverse link
+    pub fn insert_link(&mut self, link: Link) -> Result<(), GraphError> {
         let (source_id, target_id) = link;
-        let source = self.nodes.get_mut(&source_id).unwrap();
+        let source = self
+            .nodes
+            .get_mut(&source_id)
+            .ok_or_else(|| GraphError::NodeNotFound(source_id.clone()))?;
         source.neighbours.push(target_id.clone());
         self.links
             .insert((source_id, target_id), source.neighbours.len() - 1);
+        return Ok(());
     }
 
-    pub fn remove_link(&mut self, link: &Link) {
-        let index = self.links.get(&link).unwrap().clone();
-        self.links.remove(&link);
+    /// Removing a link in either direction also drops all its parallel edges
+    pub fn remove_link(&mut self, link: &Link) -> Result<(), GraphError> {
+        let index = self
+            .links
+            .remove(link)
+            .ok_or_else(|| GraphError::LinkNotFound(link.0.clone(), link.1.clone()))?;
+        let pair = determinist(link.0.clone(), link.1.clone());
+        self.edges.remove(&pair);
+        self.link_ranks.remove(&pair);
         let (source, _) = link;
-        let node = self.nodes.get_mut(source).unwrap();
-        if node.neighbours.len() == 0 || index == node.neighbours.len() - 1 {
+        let node = self
+            .nodes
+            .get_mut(source)
+            .ok_or_else(|| GraphError::NodeNotFound(source.clone()))?;
+        if node.neighbours.is_empty() || index == node.neighbours.len() - 1 {
             node.neighbours.swap_remove(index);
         } else {
             node.neighbours.swap_remove(index);
@@ -164,6 +420,88 @@ impl Graph {
             self.links
                 .insert((source.to_string(), swapped_node.to_string()), index);
         }
+        return Ok(());
+    }
+
+    /// Links both nodes in both directions if needed and adds the edge alongside the existing ones
+    pub fn insert_edge(&mut self, u: &str, v: &str, edge: Edge) -> Result<(), GraphError> {
+        for (source, target) in [(u, v), (v, u)] {
+            if !self.nodes.contains_key(target) {
+                return Err(GraphError::NodeNotFound(target.to_string()));
+            }
+            if !self.contains_link((source, target)) {
+                self.insert_link((source.to_string(), target.to_string()))?;
+            }
+        }
+        let pair = determinist(u.to_string(), v.to_string());
+        if !self.link_ranks.contains_key(&pair) {
+            self.link_ranks.insert(pair.clone(), self.next_rank);
+            self.next_rank += 1;
+        }
+        self.edges.entry(pair).or_default().push(edge);
+        return Ok(());
+    }
+
+    pub fn get_edges(&self, u: &str, v: &str) -> &[Edge] {
+        return match self.edges.get(&determinist(u.to_string(), v.to_string())) {
+            Some(edges) => edges,
+            None => &[],
+        };
+    }
+
+    /// Length of the shortest street between both nodes, or their distance if they are not linked
+    pub fn length(&self, u: &str, v: &str) -> Result<f64, GraphError> {
+        return match self
+            .get_edges(u, v)
+            .iter()
+            .map(|edge| edge.length)
+            .reduce(f64::min)
+        {
+            Some(length) => Ok(length),
+            None => self.distance(u, v),
+        };
+    }
+
+    /// Haversine distance between both nodes in meters
+    pub fn distance(&self, u: &str, v: &str) -> Result<f64, GraphError> {
+        return Ok(haversine_distance(
+            &self.get_node(u)?.point()?,
+            &self.get_node(v)?.point()?,
+        ));
+    }
+
+    /// Identifies an edge by its determinist pair of nodes, suffixed by a counter if the pair already has edges
+    pub fn link_id(&self, u: &str, v: &str) -> String {
+        let (source, target) = determinist(u.to_string(), v.to_string());
+        let base = format!("{}-{}", source, target);
+        let used: HashSet<&str> = self
+            .get_edges(u, v)
+            .iter()
+            .map(|edge| edge.id.as_str())
+            .collect();
+        let mut id = base.clone();
+        let mut counter = 1;
+        while used.contains(id.as_str()) {
+            id = format!("{}~{}", base, counter);
+            counter += 1;
+        }
+        return id;
+    }
+
+    /// Records a command and its parameters in the header, along with the ophois version
+    pub fn annotate(&mut self, command: &str) {
+        self.header.push(provenance(command));
+    }
+
+    /// Number of edges, parallel edges included
+    pub fn edges_count(&self) -> usize {
+        return self.edges.values().map(|edges| edges.len()).sum();
+    }
+}
+
+impl Default for Graph {
+    fn default() -> Graph {
+        return Graph::new();
     }
 }
 
@@ -185,9 +523,11 @@ mod tests {
         let v = "v".to_string();
         graph.insert_node(Node::_new(u.clone()));
         graph.insert_node(Node::_new(v.clone()));
-        graph.insert_link((u.clone(), v.clone()));
-        graph.insert_link((v.clone(), u.clone()));
-        assert_eq!(graph.get_node(&u).neighbours[0], v);
+        graph.insert_link((u.clone(), v.clone())).unwrap();
+        graph.insert_link((v.clone(), u.clone())).unwrap();
+        assert_eq!(graph.get_node(&u).unwrap().neighbours[0], v);
+        assert!(graph.get_node(&u).unwrap().point().is_err());
+        assert!(graph.distance(&u, &v).is_err());
     }
 
     #[test]
@@ -197,8 +537,8 @@ mod tests {
         let v = "v".to_string();
         graph.insert_node(Node::_new(u.clone()));
         graph.insert_node(Node::_new(v.clone()));
-        graph.insert_link((u.clone(), v.clone()));
-        graph.insert_link((v.clone(), u.clone()));
+        graph.insert_link((u.clone(), v.clone())).unwrap();
+        graph.insert_link((v.clone(), u.clone())).unwrap();
         assert!(graph.contains_link((&u, &v)));
     }
 
@@ -211,10 +551,10 @@ mod tests {
         graph.insert_node(Node::_new(u.clone()));
         graph.insert_node(Node::_new(v.clone()));
         graph.insert_node(Node::_new(w.clone()));
-        graph.insert_link((u.clone(), v.clone()));
-        graph.insert_link((v.clone(), u.clone()));
-        graph.insert_link((v.clone(), w.clone()));
-        graph.insert_link((w.clone(), v.clone()));
+        graph.insert_link((u.clone(), v.clone())).unwrap();
+        graph.insert_link((v.clone(), u.clone())).unwrap();
+        graph.insert_link((v.clone(), w.clone())).unwrap();
+        graph.insert_link((w.clone(), v.clone())).unwrap();
         assert_eq!(graph.links.contains_key(&(u.clone(), v.clone())), true);
         assert_eq!(graph.links.contains_key(&(v.clone(), u.clone())), true);
         assert_eq!(graph.links.contains_key(&(v.clone(), w.clone())), true);
@@ -229,7 +569,7 @@ mod tests {
         let mut graph = Graph::new();
         let node_id = "u".to_string();
         graph.insert_node(Node::_new(node_id.clone()));
-        graph.remove_node(&node_id);
+        graph.remove_node(&node_id).unwrap();
         let is_contained = graph.nodes.contains_key(&node_id);
         assert_eq!(is_contained, false);
     }
@@ -242,18 +582,181 @@ mod tests {
         graph.insert_node(Node::_new(u.clone()));
         graph.insert_node(Node::_new(v.clone()));
         graph.insert_node(Node::_new(w.clone()));
-        graph.insert_link((u.clone(), v.clone()));
-        graph.insert_link((v.clone(), u.clone()));
-        graph.insert_link((v.clone(), w.clone()));
-        graph.insert_link((w.clone(), v.clone()));
-        graph.remove_node(&v);
+        graph.insert_link((u.clone(), v.clone())).unwrap();
+        graph.insert_link((v.clone(), u.clone())).unwrap();
+        graph.insert_link((v.clone(), w.clone())).unwrap();
+        graph.insert_link((w.clone(), v.clone())).unwrap();
+        graph.remove_node(&v).unwrap();
         let empty_vector: Vec<String> = vec![];
-        assert_eq!(graph.get_node(&u).neighbours, empty_vector);
-        assert_eq!(graph.get_node(&w).neighbours, empty_vector);
+        assert_eq!(graph.get_node(&u).unwrap().neighbours, empty_vector);
+        assert_eq!(graph.get_node(&w).unwrap().neighbours, empty_vector);
         assert_eq!(graph.nodes.contains_key(&v), false);
         assert_eq!(graph.links.contains_key(&(u.clone(), v.clone())), false);
         assert_eq!(graph.links.contains_key(&(v.clone(), u.clone())), false);
         assert_eq!(graph.links.contains_key(&(v.clone(), w.clone())), false);
         assert_eq!(graph.links.contains_key(&(w, v)), false);
     }
+
+    #[test]
+    fn test_parallel_edges() {
+        let mut graph = Graph::_from(
+            "u␟48.8275185␟2.3484976\nv␟48.8275416␟2.3486683\nu␟v␟a␟12.5\nv␟u␟b␟20\nu␟v␟a␟12.5",
+            '␟',
+        )
+        .unwrap();
+        assert_eq!(
+            graph.get_node("u").unwrap().neighbours,
+            vec!["v".to_string()]
+        );
+        assert_eq!(graph.get_edges("v", "u").len(), 2);
+        assert_eq!(graph.edges_count(), 2);
+        assert_eq!(graph.length("u", "v").unwrap(), 12.5);
+        assert_eq!(graph.link_id("u", "v"), "u-v");
+        graph
+            .insert_edge(
+                "u",
+                "v",
+                Edge {
+                    id: graph.link_id("u", "v"),
+                    length: 30.0,
+                },
+            )
+            .unwrap();
+        assert_eq!(graph.link_id("v", "u"), "u-v~1");
+        graph
+            .remove_link(&("u".to_string(), "v".to_string()))
+            .unwrap();
+        graph
+            .remove_link(&("v".to_string(), "u".to_string()))
+            .unwrap();
+        assert_eq!(graph.edges_count(), 0);
+    }
+
+    #[test]
+    fn test_from_errors() {
+        let error = Graph::_from("u␟48.8275185␟2.3484976\nu␟v", '␟').unwrap_err();
+        assert_eq!(error.to_string(), "line 2: node v not found\nu␟v");
+        let error = Graph::_from("u␟48.8275185", '␟').unwrap_err();
+        assert!(matches!(error, GraphError::Line { number: 1, .. }));
+        let error = Graph::_from("u␟north␟2.3484976", '␟').unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1: invalid coordinate north\nu␟north␟2.3484976"
+        );
+        let error =
+            Graph::_from("u␟48.8275185␟2.3484976\nu␟48.8275185␟2.3484977", '␟').unwrap_err();
+        assert!(matches!(error, GraphError::Line { number: 2, .. }));
+        assert!(Graph::_from("u␟48.8275185␟2.3484976\nu␟48.82751850␟2.3484976", '␟').is_ok());
+    }
+
+    #[test]
+    fn test_read_lenient() {
+        let input = "u␟48.8275185␟2.3484976\nu␟v\nv␟48.8275416␟2.3486683\nu␟v␟u-v␟x\nu␟v";
+        let (graph, errors) = Graph::read_lenient(input.as_bytes(), '␟').unwrap();
+        assert_eq!(graph.edges_count(), 1);
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], GraphError::Line { number: 2, .. }));
+        assert!(matches!(errors[1], GraphError::Line { number: 4, .. }));
+    }
+
+    #[test]
+    fn test_missing_node_and_link() {
+        let mut graph = Graph::new();
+        assert!(matches!(
+            graph.remove_node("u"),
+            Err(GraphError::NodeNotFound(_))
+        ));
+        graph.insert_node(Node::_new("u".to_string()));
+        assert!(matches!(
+            graph.remove_link(&("u".to_string(), "v".to_string())),
+            Err(GraphError::LinkNotFound(_, _))
+        ));
+        assert!(matches!(
+            graph.insert_link(("v".to_string(), "u".to_string())),
+            Err(GraphError::NodeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_sorted_nodes_and_pairs() {
+        let graph = Graph::_from(
+            "10␟48.8275185␟2.3484976\n9␟48.8275416␟2.3486683\n100␟48.8276001␟2.3486802\n100␟9\n10␟9",
+            '␟',
+        )
+        .unwrap();
+        let ids = |order| -> Vec<&str> {
+            graph
+                .sorted_nodes(order)
+                .iter()
+                .map(|node| node.id.as_str())
+                .collect()
+        };
+        assert_eq!(ids(Order::Natural), vec!["9", "10", "100"]);
+        assert_eq!(ids(Order::Insertion), vec!["10", "9", "100"]);
+        let pairs = |order| -> Vec<(String, String)> {
+            graph.sorted_pairs(order).into_iter().cloned().collect()
+        };
+        assert_eq!(
+            pairs(Order::Natural),
+            vec![
+                ("10".to_string(), "9".to_string()),
+                ("100".to_string(), "9".to_string())
+            ]
+        );
+        assert_eq!(
+            pairs(Order::Insertion),
+            vec![
+                ("100".to_string(), "9".to_string()),
+                ("10".to_string(), "9".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_header() {
+        let mut graph = Graph::_from(
+            "# ophois 0.3.0 extract separator='␟'\nu␟48.8275185␟2.3484976\n#discretize",
+            '␟',
+        )
+        .unwrap();
+        graph.annotate("simplify delta=10");
+        assert_eq!(graph.nodes.len(), 1);
+        assert_eq!(graph.header[0], "ophois 0.3.0 extract separator='␟'");
+        assert_eq!(graph.header[1], "discretize");
+        assert!(graph.header[2].starts_with("ophois "));
+        assert!(graph.header[2].ends_with(" simplify delta=10"));
+    }
+
+    #[test]
+    fn test_escaped_fields() {
+        let mut graph = Graph::new();
+        for id in ["a-b", "#c d"] {
+            graph.insert_node(Node {
+                id: id.to_string(),
+                latitude: "48.8275185".to_string(),
+                longitude: "2.3484976".to_string(),
+                neighbours: Vec::new(),
+            });
+        }
+        let edge = Edge {
+            id: "a\\b\nc".to_string(),
+            length: 1.5,
+        };
+        graph.insert_edge("a-b", "#c d", edge).unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        graph.write(&mut output, ' ', Order::Natural).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "\\#c\\ d 48.8275185 2.3484976\na-b 48.8275185 2.3484976\n\\#c\\ d a-b a\\\\b\\nc 1.5\n"
+        );
+        let read = Graph::_from(&output, ' ').unwrap();
+        assert!(read.nodes.contains_key("#c d"));
+        assert_eq!(read.get_edges("a-b", "#c d")[0].id, "a\\b\nc");
+        assert!(read.header.is_empty());
+        assert!(Graph::_from("a\\x 48.8275185 2.3484976", ' ').is_err());
+        assert!(Graph::_from("a 48.8275185 2.3484976\\", ' ').is_err());
+        assert!(Graph::_from("a\\48.8275185\\2.3484976", '\\').is_err());
+        assert!(graph.write(&mut Vec::new(), 'n', Order::Natural).is_err());
+    }
 }
@@ -0,0 +1,242 @@
+use crate::{escape_xml, Edge, Graph, GraphError, Node, Order};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// Writes an undirected GraphML graph: nodes hold their latitude and longitude, edges their id
+/// and length, the header is kept as a graph attribute
+pub fn write_graphml(
+    graph: &Graph,
+    writer: &mut impl Write,
+    order: Order,
+) -> Result<(), GraphError> {
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(writer, "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">")?;
+    for (key, domain, kind) in [
+        ("header", "graph", "string"),
+        ("latitude", "node", "double"),
+        ("longitude", "node", "double"),
+        ("length", "edge", "double"),
+    ] {
+        writeln!(
+            writer,
+            "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+            key, domain, key, kind
+        )?;
+    }
+    writeln!(writer, "  <graph id=\"G\" edgedefault=\"undirected\">")?;
+    if !graph.header.is_empty() {
+        writeln!(
+            writer,
+            "    <data key=\"header\">{}</data>",
+            escape_xml(&graph.header.join("\n"))
+        )?;
+    }
+    for node in graph.sorted_nodes(order) {
+        writeln!(
+            writer,
+            "    <node id=\"{}\"><data key=\"latitude\">{}</data><data key=\"longitude\">{}</data></node>",
+            escape_xml(&node.id),
+            escape_xml(&node.latitude),
+            escape_xml(&node.longitude)
+        )?;
+    }
+    for (source, target) in graph.sorted_pairs(order) {
+        for edge in graph.sorted_edges(source, target, order) {
+            writeln!(
+                writer,
+                "    <edge id=\"{}\" source=\"{}\" target=\"{}\"><data key=\"length\">{}</data></edge>",
+                escape_xml(&edge.id),
+                escape_xml(source),
+                escape_xml(target),
+                edge.length
+            )?;
+        }
+    }
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Reads a GraphML graph such as written by write_graphml: data are matched by the attr.name of
+/// their key, nodes need a latitude and a longitude, edges lacking an id or a length get the link
+/// id and the distance between their nodes, a repeated node id must keep its coordinates
+pub fn read_graphml(reader: impl BufRead) -> Result<Graph, GraphError> {
+    let mut reader = Reader::from_reader(reader);
+    reader.expand_empty_elements(true);
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut names: HashMap<String, String> = HashMap::new();
+    let mut header: Option<String> = None;
+    // attributes and data of each node and edge, in document order
+    let mut nodes: Vec<(HashMap<String, String>, HashMap<String, String>)> = Vec::new();
+    let mut edges: Vec<(HashMap<String, String>, HashMap<String, String>)> = Vec::new();
+    let mut element: Option<(HashMap<String, String>, HashMap<String, String>)> = None;
+    let mut key: Option<String> = None;
+    loop {
+        match reader.read_event(&mut buffer).map_err(invalid)? {
+            Event::Start(start) => {
+                let mut attributes = attributes(&start, &reader)?;
+                match start.local_name() {
+                    b"key" => {
+                        if let Some(id) = attributes.remove("id") {
+                            let name = attributes.remove("attr.name").unwrap_or_else(|| id.clone());
+                            names.insert(id, name);
+                        }
+                    }
+                    b"node" | b"edge" => element = Some((attributes, HashMap::new())),
+                    b"data" => {
+                        key = attributes
+                            .remove("key")
+                            .map(|key| names.get(&key).cloned().unwrap_or(key))
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(text) => {
+                if let Some(key) = &key {
+                    let value = text.unescape_and_decode(&reader).map_err(invalid)?;
+                    match &mut element {
+                        Some((_, data)) => {
+                            data.insert(key.clone(), value);
+                        }
+                        None if key == "header" => header = Some(value),
+                        None => {}
+                    }
+                }
+            }
+            Event::End(end) => match end.local_name() {
+                b"data" => key = None,
+                b"node" => nodes.extend(element.take()),
+                b"edge" => edges.extend(element.take()),
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buffer.clear();
+    }
+    let mut graph = Graph::new();
+    if let Some(header) = header {
+        graph.header = header.lines().map(|line| line.to_string()).collect();
+    }
+    for (attributes, data) in nodes {
+        let id = required(&attributes, "id", "node")?;
+        let mut coordinates: Vec<String> = Vec::new();
+        for name in ["latitude", "longitude"] {
+            match data
+                .get(name)
+                .filter(|value| value.trim().parse::<f64>().is_ok())
+            {
+                Some(value) => coordinates.push(value.trim().to_string()),
+                None => {
+                    return Err(GraphError::InvalidRecord(format!(
+                        "node {} has no valid {}",
+                        id, name
+                    )))
+                }
+            }
+        }
+        graph.insert_checked_node(Node {
+            id,
+            latitude: coordinates[0].clone(),
+            longitude: coordinates[1].clone(),
+            neighbours: Vec::new(),
+        })?;
+    }
+    for (attributes, data) in edges {
+        let source = required(&attributes, "source", "edge")?;
+        let target = required(&attributes, "target", "edge")?;
+        let length = match data.get("length") {
+            Some(length) => length
+                .trim()
+                .parse()
+                .map_err(|_| GraphError::InvalidRecord(format!("invalid length {}", length)))?,
+            None => graph.distance(&source, &target)?,
+        };
+        let edge = Edge {
+            id: match attributes.get("id") {
+                Some(id) => id.clone(),
+                None => graph.link_id(&source, &target),
+            },
+            length,
+        };
+        graph.insert_edge(&source, &target, edge)?;
+    }
+    return Ok(graph);
+}
+
+fn attributes<B: BufRead>(
+    start: &BytesStart,
+    reader: &Reader<B>,
+) -> Result<HashMap<String, String>, GraphError> {
+    let mut attributes: HashMap<String, String> = HashMap::new();
+    for attribute in start.attributes() {
+        let attribute = attribute.map_err(invalid)?;
+        let value = attribute
+            .unescape_and_decode_value(reader)
+            .map_err(invalid)?;
+        attributes.insert(String::from_utf8_lossy(attribute.key).to_string(), value);
+    }
+    return Ok(attributes);
+}
+
+fn required(
+    attributes: &HashMap<String, String>,
+    name: &str,
+    element: &str,
+) -> Result<String, GraphError> {
+    return attributes
+        .get(name)
+        .cloned()
+        .ok_or_else(|| GraphError::InvalidRecord(format!("{} without {}", element, name)));
+}
+
+fn invalid(error: quick_xml::Error) -> GraphError {
+    return GraphError::InvalidRecord(format!("invalid GraphML {}", error));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_write_graphml() {
+        let graph = Graph::_from("# ophois 0.3.0 extract separator='␟' area=\"Paris & co\"\n3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637489␟3761637488␟3761637488-3761637489␟2.5\n3761637489␟3761637488␟3761637488-3761637489~1␟3", '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_graphml(&graph, &mut output, Order::Natural).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("<data key=\"header\">ophois 0.3.0 extract separator=&apos;␟&apos; area=&quot;Paris &amp; co&quot;</data>"));
+        assert!(output.contains("<node id=\"3761637488\"><data key=\"latitude\">48.8275416</data><data key=\"longitude\">2.3486683</data></node>"));
+        assert!(output.contains("<edge id=\"3761637488-3761637489~1\" source=\"3761637488\" target=\"3761637489\"><data key=\"length\">3</data></edge>"));
+        assert_eq!(output.matches("<edge ").count(), 2);
+        assert!(output.ends_with("</graph>\n</graphml>\n"));
+    }
+
+    #[test]
+    fn test_read_graphml() {
+        let graph = Graph::_from("# ophois 0.3.0 extract separator='␟' area=\"Paris & co\"\n# ophois 0.3.0 simplify separator='␟' delta=10\n3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637489␟3761637488␟3761637488-3761637489␟2.5\n3761637489␟3761637488␟a<b␟3", '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_graphml(&graph, &mut output, Order::Natural).unwrap();
+        let read = read_graphml(output.as_slice()).unwrap();
+        let mut expected: Vec<u8> = Vec::new();
+        graph.write(&mut expected, '␟', Order::Natural).unwrap();
+        let mut actual: Vec<u8> = Vec::new();
+        read.write(&mut actual, '␟', Order::Natural).unwrap();
+        assert_eq!(
+            String::from_utf8(actual).unwrap(),
+            String::from_utf8(expected).unwrap()
+        );
+        let networkx = r#"<graphml><key id="d0" for="node" attr.name="latitude" attr.type="double"/><key id="d1" for="node" attr.name="longitude" attr.type="double"/><graph edgedefault="undirected"><node id="1"><data key="d0">48.8275416</data><data key="d1">2.3486683</data></node><node id="2"><data key="d0">48.8275453</data><data key="d1">2.348698</data></node><edge source="1" target="2"/></graph></graphml>"#;
+        let read = read_graphml(networkx.as_bytes()).unwrap();
+        assert_eq!(read.get_edges("1", "2")[0].id, "1-2");
+        assert!(
+            read_graphml("<graphml><graph><node id=\"1\"/></graph></graphml>".as_bytes()).is_err()
+        );
+        let duplicate = networkx.replace("<node id=\"2\">", "<node id=\"1\">");
+        assert!(matches!(
+            read_graphml(duplicate.as_bytes()),
+            Err(GraphError::InvalidRecord(_))
+        ));
+    }
+}
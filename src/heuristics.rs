@@ -1,19 +1,114 @@
-use crate::Graph;
 use crate::Node;
-use crate::{haversine_distance, midpoint};
+use crate::{haversine_distance, midpoint, natural_cmp};
+use crate::{Edge, Graph, GraphError, Operation, Order};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::SeedableRng;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
 
-pub fn bfs_largest_component(graph: Graph) -> Graph {
+/// Order in which under delta nodes and links are considered
+pub enum Strategy {
+    /// Reproducible when the generator is seeded
+    Random(Box<StdRng>),
+    /// Shortest links first, nodes by their longest link, ties in natural id order
+    Shortest,
+}
+
+/// Name of a strategy as given on the command line
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StrategyKind {
+    Random,
+    Shortest,
+}
+
+impl FromStr for StrategyKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<StrategyKind, String> {
+        return match kind {
+            "random" => Ok(StrategyKind::Random),
+            "shortest" => Ok(StrategyKind::Shortest),
+            _ => Err(format!(
+                "unknown strategy {}, expected random or shortest",
+                kind
+            )),
+        };
+    }
+}
+
+impl fmt::Display for StrategyKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            StrategyKind::Random => write!(f, "random"),
+            StrategyKind::Shortest => write!(f, "shortest"),
+        };
+    }
+}
+
+impl Strategy {
+    /// Random order, seeded from entropy without a seed
+    pub fn random(seed: Option<u64>) -> Strategy {
+        return Strategy::Random(match seed {
+            Some(seed) => Box::new(StdRng::seed_from_u64(seed)),
+            None => Box::new(StdRng::from_entropy()),
+        });
+    }
+
+    fn arrange_nodes(&mut self, graph: &Graph, nodes: &mut [String]) -> Result<(), GraphError> {
+        nodes.sort_by(|a, b| natural_cmp(a, b));
+        match self {
+            Strategy::Random(rng) => nodes.shuffle(rng),
+            Strategy::Shortest => {
+                let mut longest: HashMap<String, f64> = HashMap::new();
+                for node_id in nodes.iter() {
+                    let mut length: f64 = 0.0;
+                    for neighbour_id in &graph.get_node(node_id)?.neighbours {
+                        length = length.max(graph.distance(node_id, neighbour_id)?);
+                    }
+                    longest.insert(node_id.clone(), length);
+                }
+                nodes.sort_by(|a, b| longest[a].total_cmp(&longest[b]));
+            }
+        }
+        return Ok(());
+    }
+
+    fn arrange_links(
+        &mut self,
+        graph: &Graph,
+        links: &mut [(String, String)],
+    ) -> Result<(), GraphError> {
+        links.sort_by(|a, b| natural_cmp(&a.0, &b.0).then(natural_cmp(&a.1, &b.1)));
+        match self {
+            Strategy::Random(rng) => links.shuffle(rng),
+            Strategy::Shortest => {
+                let mut distances: HashMap<(String, String), f64> = HashMap::new();
+                for (u, v) in links.iter() {
+                    distances.insert((u.clone(), v.clone()), graph.distance(u, v)?);
+                }
+                links.sort_by(|a, b| distances[a].total_cmp(&distances[b]));
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Keeps the component with the most nodes, ties go to the first one in natural id order
+pub fn bfs_largest_component(graph: Graph) -> Result<Graph, GraphError> {
     let mut queue: VecDeque<String> = VecDeque::new();
     let mut visited: HashSet<String> = HashSet::new();
     let mut distribution: HashMap<u32, u32> = HashMap::new();
     let mut largest_component: Graph = Graph::new();
     let mut largest_component_size: u32 = 0;
-    for (node_id, _) in graph.nodes.iter() {
+    for node_id in graph
+        .sorted_nodes(Order::Natural)
+        .iter()
+        .map(|node| &node.id)
+    {
         if !visited.contains(node_id) {
             let mut component_size: u32 = 0;
             let mut current_component_nodes: HashMap<String, Node> = HashMap::new();
@@ -21,7 +116,7 @@ pub fn bfs_largest_component(graph: Graph) -> Graph {
             visited.insert(node_id.clone());
             while !queue.is_empty() {
                 component_size += 1;
-                let node = graph.get_node(&queue.pop_front().unwrap());
+                let node = graph.get_node(&queue.pop_front().unwrap())?;
                 current_component_nodes.insert(
                     node.id.clone(),
                     Node {
@@ -47,125 +142,283 @@ pub fn bfs_largest_component(graph: Graph) -> Graph {
         }
     }
     for (node_id, _) in largest_component.nodes.clone() {
-        let old_neighbours = graph.get_node(&node_id).neighbours.clone();
+        let old_neighbours = graph.get_node(&node_id)?.neighbours.clone();
         for neighbour_id in old_neighbours {
             if largest_component.nodes.contains_key(&neighbour_id) {
-                largest_component.insert_link((node_id.clone(), neighbour_id.clone()));
+                largest_component.insert_link((node_id.clone(), neighbour_id.clone()))?;
             }
         }
     }
-    return largest_component;
+    for (pair, edges) in graph.edges {
+        if largest_component.nodes.contains_key(&pair.0) {
+            largest_component.edges.insert(pair, edges);
+        }
+    }
+    largest_component.node_ranks = graph
+        .node_ranks
+        .into_iter()
+        .filter(|(node_id, _)| largest_component.nodes.contains_key(node_id))
+        .collect();
+    largest_component.link_ranks = graph
+        .link_ranks
+        .into_iter()
+        .filter(|(pair, _)| largest_component.edges.contains_key(pair))
+        .collect();
+    largest_component.origins = graph
+        .origins
+        .into_iter()
+        .filter(|(node_id, _)| largest_component.nodes.contains_key(node_id))
+        .collect();
+    largest_component.next_rank = graph.next_rank;
+    largest_component.header = graph.header;
+    return Ok(largest_component);
 }
 
-pub fn replace_node_by_links(mut graph: Graph, node_id: &str) -> Graph {
-    let neighbours = graph.nodes.get(node_id).unwrap().neighbours.clone();
-    graph.remove_node(&node_id);
+/// A degree two node is a street passing through, so its replacement stays parallel to any existing link.
+/// Each combination of parallel edges on both sides becomes one edge whose length is their sum
+pub fn replace_node_by_links(mut graph: Graph, node_id: &str) -> Result<Graph, GraphError> {
+    let neighbours = graph.get_node(node_id)?.neighbours.clone();
+    let mut lengths: Vec<Vec<f64>> = Vec::new();
+    for neighbour_id in &neighbours {
+        let edges = graph.get_edges(node_id, neighbour_id);
+        if edges.is_empty() {
+            lengths.push(vec![graph.distance(node_id, neighbour_id)?]);
+        } else {
+            lengths.push(edges.iter().map(|edge| edge.length).collect());
+        }
+    }
+    graph.remove_node(node_id)?;
     for i in 0..neighbours.len() {
         for j in i + 1..neighbours.len() {
-            if !graph
-                .links
-                .contains_key(&(neighbours[i].clone(), neighbours[j].clone()))
-                && !graph
-                    .links
-                    .contains_key(&(neighbours[j].clone(), neighbours[i].clone()))
-            {
-                graph.insert_link((neighbours[i].clone(), neighbours[j].clone()));
-                graph.insert_link((neighbours[j].clone(), neighbours[i].clone()));
+            if neighbours.len() == 2 || !graph.contains_link((&neighbours[i], &neighbours[j])) {
+                for first in &lengths[i] {
+                    for second in &lengths[j] {
+                        let edge = Edge {
+                            id: graph.link_id(&neighbours[i], &neighbours[j]),
+                            length: first + second,
+                        };
+                        graph.insert_edge(&neighbours[i], &neighbours[j], edge)?;
+                    }
+                }
             }
         }
     }
-    return graph;
+    return Ok(graph);
 }
 
-pub fn remove_degree_two_nodes(mut graph: Graph) -> Graph {
+/// Replaces each node of degree two by a link between its neighbours
+pub fn remove_degree_two_nodes(mut graph: Graph) -> Result<Graph, GraphError> {
     let mut degree_two_nodes: Vec<String> = Vec::new();
     for (node_id, node) in graph.nodes.iter() {
         if node.neighbours.len() == 2 {
             degree_two_nodes.push(node_id.clone());
         }
     }
+    degree_two_nodes.sort_by(|a, b| natural_cmp(a, b));
     for to_delete in degree_two_nodes {
-        graph = replace_node_by_links(graph, &to_delete);
+        graph = replace_node_by_links(graph, &to_delete)?;
     }
-    return graph;
+    return Ok(graph);
 }
 
-pub fn remove_under_delta_nodes(mut graph: Graph, delta: f64) -> Graph {
+/// Replaces nodes whose links are all under delta meters by links between their neighbours
+pub fn remove_under_delta_nodes(
+    mut graph: Graph,
+    delta: f64,
+    strategy: &mut Strategy,
+) -> Result<Graph, GraphError> {
     let mut nodes: Vec<String> = graph.nodes.keys().cloned().collect();
-    nodes.shuffle(&mut thread_rng());
+    strategy.arrange_nodes(&graph, &mut nodes)?;
     for node_id in nodes {
-        let node = graph.get_node(&node_id);
+        let node = graph.get_node(&node_id)?;
         let mut remove = true;
         for neighbour_id in &node.neighbours {
-            let neighbour = graph.get_node(neighbour_id);
-            let distance = haversine_distance(&node.point(), &neighbour.point());
+            let neighbour = graph.get_node(neighbour_id)?;
+            let distance = haversine_distance(&node.point()?, &neighbour.point()?);
             if distance > delta {
                 remove = false;
                 break;
             }
         }
         if remove {
-            graph = replace_node_by_links(graph, &node_id);
+            graph = replace_node_by_links(graph, &node_id)?;
         }
     }
-    return graph;
+    return Ok(graph);
 }
 
-pub fn replace_link_by_node(mut graph: Graph, link: &(String, String)) -> Graph {
-    let source = graph.get_node(&link.0).clone();
-    let target = graph.get_node(&link.1).clone();
-    graph.remove_node(&source.id);
-    graph.remove_node(&target.id);
-    let mut neighbours = [&source.neighbours[..], &target.neighbours[..]].concat();
-    neighbours.sort_unstable();
-    neighbours.dedup();
-    neighbours.retain(|x| *x != source.id && *x != target.id);
+/// Edges of both merged nodes keep their id and get half the length of the merged link, the shortest
+/// of its parallel edges, as both nodes move half way to the midpoint. Common neighbours end up with
+/// parallel edges
+pub fn replace_link_by_node(
+    mut graph: Graph,
+    link: &(String, String),
+) -> Result<Graph, GraphError> {
+    let source = graph.get_node(&link.0)?.clone();
+    let target = graph.get_node(&link.1)?.clone();
+    let half = graph.length(&source.id, &target.id)? / 2.0;
+    let mut edges: Vec<(String, Edge)> = Vec::new();
+    for (node, other) in [(&source, &target), (&target, &source)] {
+        for neighbour_id in node.neighbours.iter().filter(|x| **x != other.id) {
+            for edge in graph.get_edges(&node.id, neighbour_id) {
+                let edge = Edge {
+                    id: edge.id.clone(),
+                    length: edge.length + half,
+                };
+                edges.push((neighbour_id.clone(), edge));
+            }
+        }
+    }
     let determinist_link = if source.id < target.id {
         (source.id.to_owned(), target.id.to_owned())
     } else {
         (target.id.to_owned(), source.id.to_owned())
     };
     let new_node_id = format!("{}-{}", determinist_link.0, determinist_link.1);
-    let midpoint = midpoint(&source.point(), &target.point());
+    graph.derive(&new_node_id, Operation::Merge, &[&source.id, &target.id]);
+    graph.remove_node(&source.id)?;
+    graph.remove_node(&target.id)?;
+    let midpoint = midpoint(&source.point()?, &target.point()?);
     graph.insert_node(Node {
         id: new_node_id.clone(),
         longitude: midpoint.x.to_string(),
         latitude: midpoint.y.to_string(),
         neighbours: Vec::new(),
     });
-    for neighbour_id in neighbours {
-        graph.insert_link((new_node_id.clone(), neighbour_id.clone()));
-        graph.insert_link((neighbour_id.clone(), new_node_id.clone()));
+    for (neighbour_id, edge) in edges {
+        graph.insert_edge(&new_node_id, &neighbour_id, edge)?;
     }
-    return graph;
+    return Ok(graph);
 }
 
-pub fn remove_under_delta_links(mut graph: Graph, delta: f64) -> Graph {
+/// Merges the nodes of links under delta meters into their midpoint until no such link remains
+pub fn remove_under_delta_links(
+    mut graph: Graph,
+    delta: f64,
+    strategy: &mut Strategy,
+) -> Result<Graph, GraphError> {
     let mut is_below_delta = true;
     while is_below_delta {
         let mut links: Vec<(String, String)> = graph.links.keys().cloned().collect();
-        links.shuffle(&mut thread_rng());
+        strategy.arrange_links(&graph, &mut links)?;
         for link in links.iter() {
             if graph.links.contains_key(&(link.1.clone(), link.0.clone())) {
-                let source = graph.get_node(&link.0);
-                let target = graph.get_node(&link.1);
-                let distance = haversine_distance(&source.point(), &target.point());
+                let source = graph.get_node(&link.0)?;
+                let target = graph.get_node(&link.1)?;
+                let distance = haversine_distance(&source.point()?, &target.point()?);
                 if distance < delta {
-                    graph = replace_link_by_node(graph, link);
+                    graph = replace_link_by_node(graph, link)?;
                 }
             }
         }
         is_below_delta = false;
         for (link, _) in graph.links.iter() {
-            let source = graph.get_node(&link.0);
-            let target = graph.get_node(&link.1);
-            let distance = haversine_distance(&source.point(), &target.point());
+            let source = graph.get_node(&link.0)?;
+            let target = graph.get_node(&link.1)?;
+            let distance = haversine_distance(&source.point()?, &target.point()?);
             if distance < delta {
                 is_below_delta = true;
             }
         }
     }
-    return graph;
+    return Ok(graph);
+}
+
+/// Stage of a simplification, written largest, deg2, nodes:delta or links:delta
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Step {
+    /// bfs_largest_component
+    Largest,
+    /// remove_degree_two_nodes
+    DegreeTwo,
+    /// remove_under_delta_nodes with its delta in meters
+    Nodes(f64),
+    /// remove_under_delta_links with its delta in meters
+    Links(f64),
+}
+
+impl Step {
+    /// Steps of simplify: largest component, degree two nodes, under delta nodes then links
+    pub fn defaults(delta: f64) -> Vec<Step> {
+        return vec![
+            Step::Largest,
+            Step::DegreeTwo,
+            Step::Nodes(delta),
+            Step::Links(delta),
+        ];
+    }
+
+    /// Parses comma separated steps, nodes and links without delta use the given delta
+    pub fn parse_list(steps: &str, delta: Option<f64>) -> Result<Vec<Step>, String> {
+        return steps
+            .split(',')
+            .map(|step| match (step.trim(), delta) {
+                ("nodes", Some(delta)) => Ok(Step::Nodes(delta)),
+                ("links", Some(delta)) => Ok(Step::Links(delta)),
+                (step, _) => step.parse(),
+            })
+            .collect();
+    }
+}
+
+impl FromStr for Step {
+    type Err = String;
+
+    fn from_str(step: &str) -> Result<Step, String> {
+        let (name, delta) = match step.split_once(':') {
+            Some((name, delta)) => match delta.parse::<f64>() {
+                Ok(delta) if delta.is_finite() && delta >= 0.0 => (name, Some(delta)),
+                _ => return Err(format!("invalid delta {} of step {}", delta, name)),
+            },
+            None => (step, None),
+        };
+        return match (name, delta) {
+            ("largest", None) => Ok(Step::Largest),
+            ("deg2", None) => Ok(Step::DegreeTwo),
+            ("nodes", Some(delta)) => Ok(Step::Nodes(delta)),
+            ("links", Some(delta)) => Ok(Step::Links(delta)),
+            ("nodes" | "links", None) => {
+                Err(format!("step {} expects a delta: {}:meters", name, name))
+            }
+            _ => Err(format!(
+                "unknown step {}, expected largest, deg2, nodes:delta or links:delta",
+                step
+            )),
+        };
+    }
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Step::Largest => write!(f, "largest"),
+            Step::DegreeTwo => write!(f, "deg2"),
+            Step::Nodes(delta) => write!(f, "nodes:{}", delta),
+            Step::Links(delta) => write!(f, "links:{}", delta),
+        }
+    }
+}
+
+/// Keeps the largest component, removes degree two nodes, under delta nodes then under delta links
+pub fn simplify(graph: Graph, delta: f64, strategy: &mut Strategy) -> Result<Graph, GraphError> {
+    return simplify_steps(graph, &Step::defaults(delta), strategy);
+}
+
+/// Runs the steps in order, a step may be repeated
+pub fn simplify_steps(
+    mut graph: Graph,
+    steps: &[Step],
+    strategy: &mut Strategy,
+) -> Result<Graph, GraphError> {
+    for step in steps {
+        graph = match *step {
+            Step::Largest => bfs_largest_component(graph)?,
+            Step::DegreeTwo => remove_degree_two_nodes(graph)?,
+            Step::Nodes(delta) => remove_under_delta_nodes(graph, delta, strategy)?,
+            Step::Links(delta) => remove_under_delta_links(graph, delta, strategy)?,
+        };
+    }
+    return Ok(graph);
 }
 
 #[cfg(test)]
@@ -173,8 +426,9 @@ mod tests {
     use super::*;
     #[test]
     fn test_bfs_and_largest_component() {
-        let mut graph = Graph::_from("21658501␟48.8279975␟2.3518307\n21658502␟48.8279276␟2.3513732\n92192237␟48.8275872␟2.3490245\n1829061602␟48.8275089␟2.3484223\n1829061607␟48.8278868␟2.347252\n1829061610␟48.8260051␟2.3474783\n1829061640␟48.827773␟2.3503086\n1829061642␟48.8278201␟2.3506517\n1829061648␟48.8277624␟2.3502336\n1829061667␟48.8265177␟2.3501273\n1829061676␟48.8269249␟2.348167\n1852590201␟48.8276523␟2.3494784\n2268836829␟48.8276001␟2.3486802\n2286779145␟48.8260569␟2.3475149\n2286779154␟48.8276739␟2.3496385\n2576426847␟48.8273391␟2.3487858\n2576426850␟48.8274242␟2.3486471\n2576426851␟48.8274323␟2.3487423\n2576426852␟48.8274347␟2.3487671\n2576426853␟48.8274352␟2.348721\n2576426854␟48.8274412␟2.3487844\n2576426855␟48.827493␟2.3485442\n2576426856␟48.8275026␟2.3485468\n2576426858␟48.8275464␟2.3489207\n2576426859␟48.8275541␟2.3489099\n2597215157␟48.8265578␟2.3500902\n2598270008␟48.8276879␟2.349736\n3758221284␟48.8273411␟2.3486982\n3758221292␟48.8274025␟2.3486929\n3758221295␟48.8275185␟2.3484976\n3758221301␟48.8275751␟2.3489308\n3761637482␟48.8274512␟2.3486719\n3761637486␟48.8275249␟2.348704\n3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637490␟48.8275499␟2.348735\n3761637496␟48.8278544␟2.3473522\n6400885441␟48.8274338␟2.3488187\n6400933176␟48.8268914␟2.3481419\n1829061610␟2286779145\n2286779145␟6400933176\n6400933176␟1829061676\n1829061676␟3758221284\n3758221301␟3761637490\n92192237␟1852590201\n1852590201␟2286779154\n2286779154␟2598270008\n2598270008␟1829061648\n1829061648␟1829061640\n1829061640␟1829061642\n1829061642␟21658502\n21658502␟21658501\n3758221292␟2576426850\n1829061602␟3761637496\n3761637496␟1829061607\n1829061667␟2597215157\n2597215157␟2576426847\n2576426854␟2576426852\n2576426852␟2576426851\n2576426851␟2576426853\n2576426853␟3761637482\n3761637482␟2576426855\n2576426855␟2576426856\n2576426856␟3761637486\n3761637486␟2576426859\n2576426859␟2576426858\n2576426858␟2576426854\n3761637490␟3761637489\n3761637489␟3761637488\n3761637488␟3758221295\n2268836829␟3761637489\n3761637489␟3761637486\n3761637486␟3761637482\n3761637482␟2576426850\n3758221292␟2576426853", '␟');
-        graph = bfs_largest_component(graph);
+        let mut graph = Graph::_from("21658501␟48.8279975␟2.3518307\n21658502␟48.8279276␟2.3513732\n92192237␟48.8275872␟2.3490245\n1829061602␟48.8275089␟2.3484223\n1829061607␟48.8278868␟2.347252\n1829061610␟48.8260051␟2.3474783\n1829061640␟48.827773␟2.3503086\n1829061642␟48.8278201␟2.3506517\n1829061648␟48.8277624␟2.3502336\n1829061667␟48.8265177␟2.3501273\n1829061676␟48.8269249␟2.348167\n1852590201␟48.8276523␟2.3494784\n2268836829␟48.8276001␟2.3486802\n2286779145␟48.8260569␟2.3475149\n2286779154␟48.8276739␟2.3496385\n2576426847␟48.8273391␟2.3487858\n2576426850␟48.8274242␟2.3486471\n2576426851␟48.8274323␟2.3487423\n2576426852␟48.8274347␟2.3487671\n2576426853␟48.8274352␟2.348721\n2576426854␟48.8274412␟2.3487844\n2576426855␟48.827493␟2.3485442\n2576426856␟48.8275026␟2.3485468\n2576426858␟48.8275464␟2.3489207\n2576426859␟48.8275541␟2.3489099\n2597215157␟48.8265578␟2.3500902\n2598270008␟48.8276879␟2.349736\n3758221284␟48.8273411␟2.3486982\n3758221292␟48.8274025␟2.3486929\n3758221295␟48.8275185␟2.3484976\n3758221301␟48.8275751␟2.3489308\n3761637482␟48.8274512␟2.3486719\n3761637486␟48.8275249␟2.348704\n3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637490␟48.8275499␟2.348735\n3761637496␟48.8278544␟2.3473522\n6400885441␟48.8274338␟2.3488187\n6400933176␟48.8268914␟2.3481419\n1829061610␟2286779145\n2286779145␟6400933176\n6400933176␟1829061676\n1829061676␟3758221284\n3758221301␟3761637490\n92192237␟1852590201\n1852590201␟2286779154\n2286779154␟2598270008\n2598270008␟1829061648\n1829061648␟1829061640\n1829061640␟1829061642\n1829061642␟21658502\n21658502␟21658501\n3758221292␟2576426850\n1829061602␟3761637496\n3761637496␟1829061607\n1829061667␟2597215157\n2597215157␟2576426847\n2576426854␟2576426852\n2576426852␟2576426851\n2576426851␟2576426853\n2576426853␟3761637482\n3761637482␟2576426855\n2576426855␟2576426856\n2576426856␟3761637486\n3761637486␟2576426859\n2576426859␟2576426858\n2576426858␟2576426854\n3761637490␟3761637489\n3761637489␟3761637488\n3761637488␟3758221295\n2268836829␟3761637489\n3761637489␟3761637486\n3761637486␟3761637482\n3761637482␟2576426850\n3758221292␟2576426853", '␟')
+        .unwrap();
+        graph = bfs_largest_component(graph).unwrap();
         assert!(graph.nodes.len() == 18);
         assert!(graph.links.len() / 2 == 20);
         let expected: Vec<(&str, usize)> = vec![
@@ -199,14 +453,15 @@ mod tests {
         ];
         for (node, degree) in expected {
             assert!(graph.nodes.contains_key(node));
-            assert!(graph.get_node(node).neighbours.len() == degree);
+            assert!(graph.get_node(node).unwrap().neighbours.len() == degree);
         }
     }
 
     #[test]
     fn test_replace_node_by_links() {
-        let mut graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637486␟48.8275249␟2.348704\n3761637489␟48.8275453␟2.348698\n3761637490␟48.8275499␟2.348735\n2268836829␟48.8276001␟2.3486802\n3761637489␟3761637488\n3761637489␟2268836829\n3761637489␟3761637490\n3761637489␟3761637486", '␟');
-        graph = replace_node_by_links(graph, "3761637489");
+        let mut graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637486␟48.8275249␟2.348704\n3761637489␟48.8275453␟2.348698\n3761637490␟48.8275499␟2.348735\n2268836829␟48.8276001␟2.3486802\n3761637489␟3761637488\n3761637489␟2268836829\n3761637489␟3761637490\n3761637489␟3761637486", '␟')
+        .unwrap();
+        graph = replace_node_by_links(graph, "3761637489").unwrap();
         assert!(graph.nodes.len() == 4);
         assert!(graph.links.len() / 2 == 6);
         let expected: Vec<(&str, usize)> = vec![
@@ -217,14 +472,15 @@ mod tests {
         ];
         for (node, degree) in expected {
             assert!(graph.nodes.contains_key(node));
-            assert!(graph.get_node(node).neighbours.len() == degree);
+            assert!(graph.get_node(node).unwrap().neighbours.len() == degree);
         }
     }
 
     #[test]
     fn test_remove_degree_two_nodes() {
-        let mut graph = Graph::_from("2576426859␟48.8275541␟2.3489099\n2576426853␟48.8274352␟2.348721\n3761637489␟48.8275453␟2.348698\n2576426856␟48.8275026␟2.3485468\n3758221284␟48.8273411␟2.3486982\n92192237␟48.8275872␟2.3490245\n3761637486␟48.8275249␟2.348704\n3761637488␟48.8275416␟2.3486683\n1829061602␟48.8275089␟2.3484223\n3758221301␟48.8275751␟2.3489308\n2268836829␟48.8276001␟2.3486802\n2576426850␟48.8274242␟2.3486471\n3761637482␟48.8274512␟2.3486719\n2576426858␟48.8275464␟2.3489207\n6400885441␟48.8274338␟2.3488187\n3758221295␟48.8275185␟2.3484976\n1852590201␟48.8276523␟2.3494784\n2576426854␟48.8274412␟2.3487844\n2576426851␟48.8274323␟2.3487423\n3758221292␟48.8274025␟2.3486929\n1829061614␟48.8273732␟2.3487375\n2576426855␟48.827493␟2.3485442\n2576426852␟48.8274347␟2.3487671\n3761637490␟48.8275499␟2.348735\n3761637496␟48.8278544␟2.3473522\n2576426847␟48.8273391␟2.3487858\n3758221301␟92192237\n2576426855␟3761637482\n1829061614␟3758221284\n1829061602␟3761637496\n1852590201␟92192237\n1829061614␟6400885441\n2576426853␟3761637482\n2576426851␟2576426852\n2576426850␟3761637482\n2576426855␟2576426856\n3758221301␟3761637490\n3761637482␟3761637486\n6400885441␟92192237\n3761637488␟3761637489\n1829061614␟3758221292\n1829061602␟2576426850\n3758221295␟3761637488\n3761637486␟3761637489\n2576426853␟3758221292\n1829061614␟2576426847\n3761637489␟3761637490\n2576426858␟2576426859\n2576426856␟3761637486\n2576426851␟2576426853\n2576426859␟3761637486\n1829061602␟3758221295\n2576426852␟2576426854\n2268836829␟3761637489\n2576426850␟3758221292\n2576426854␟2576426858", '␟');
-        graph = remove_degree_two_nodes(graph);
+        let mut graph = Graph::_from("2576426859␟48.8275541␟2.3489099\n2576426853␟48.8274352␟2.348721\n3761637489␟48.8275453␟2.348698\n2576426856␟48.8275026␟2.3485468\n3758221284␟48.8273411␟2.3486982\n92192237␟48.8275872␟2.3490245\n3761637486␟48.8275249␟2.348704\n3761637488␟48.8275416␟2.3486683\n1829061602␟48.8275089␟2.3484223\n3758221301␟48.8275751␟2.3489308\n2268836829␟48.8276001␟2.3486802\n2576426850␟48.8274242␟2.3486471\n3761637482␟48.8274512␟2.3486719\n2576426858␟48.8275464␟2.3489207\n6400885441␟48.8274338␟2.3488187\n3758221295␟48.8275185␟2.3484976\n1852590201␟48.8276523␟2.3494784\n2576426854␟48.8274412␟2.3487844\n2576426851␟48.8274323␟2.3487423\n3758221292␟48.8274025␟2.3486929\n1829061614␟48.8273732␟2.3487375\n2576426855␟48.827493␟2.3485442\n2576426852␟48.8274347␟2.3487671\n3761637490␟48.8275499␟2.348735\n3761637496␟48.8278544␟2.3473522\n2576426847␟48.8273391␟2.3487858\n3758221301␟92192237\n2576426855␟3761637482\n1829061614␟3758221284\n1829061602␟3761637496\n1852590201␟92192237\n1829061614␟6400885441\n2576426853␟3761637482\n2576426851␟2576426852\n2576426850␟3761637482\n2576426855␟2576426856\n3758221301␟3761637490\n3761637482␟3761637486\n6400885441␟92192237\n3761637488␟3761637489\n1829061614␟3758221292\n1829061602␟2576426850\n3758221295␟3761637488\n3761637486␟3761637489\n2576426853␟3758221292\n1829061614␟2576426847\n3761637489␟3761637490\n2576426858␟2576426859\n2576426856␟3761637486\n2576426851␟2576426853\n2576426859␟3761637486\n1829061602␟3758221295\n2576426852␟2576426854\n2268836829␟3761637489\n2576426850␟3758221292\n2576426854␟2576426858", '␟')
+        .unwrap();
+        graph = remove_degree_two_nodes(graph).unwrap();
         assert!(graph.nodes.len() == 14);
         assert!(graph.links.len() / 2 == 17);
         let expected: Vec<(&str, usize)> = vec![
@@ -245,14 +501,15 @@ mod tests {
         ];
         for (node, degree) in expected {
             assert!(graph.nodes.contains_key(node));
-            assert!(graph.get_node(node).neighbours.len() == degree);
+            assert!(graph.get_node(node).unwrap().neighbours.len() == degree);
         }
     }
 
     #[test]
     fn test_remove_under_delta_nodes() {
-        let mut graph = Graph::_from("3758221284␟48.8273411␟2.3486982\n3761637489␟48.8275453␟2.348698\n3761637482␟48.8274512␟2.3486719\n3761637496␟48.8278544␟2.3473522\n2576426847␟48.8273391␟2.3487858\n1829061614␟48.8273732␟2.3487375\n2576426853␟48.8274352␟2.348721\n2576426850␟48.8274242␟2.3486471\n1852590201␟48.8276523␟2.3494784\n3758221292␟48.8274025␟2.3486929\n3761637486␟48.8275249␟2.348704\n2268836829␟48.8276001␟2.3486802\n92192237␟48.8275872␟2.3490245\n1829061602␟48.8275089␟2.3484223\n2576426853␟3761637486\n2576426850␟3758221292\n1829061614␟3758221292\n2268836829␟3761637489\n3761637489␟92192237\n2576426853␟3761637482\n3761637482␟3761637486\n1829061614␟3758221284\n1829061602␟3761637496\n1852590201␟92192237\n2576426850␟3761637482\n1829061602␟2576426850\n1829061614␟2576426847\n1829061602␟3761637489\n2576426853␟3758221292\n3761637486␟3761637489\n1829061614␟92192237", '␟');
-        graph = remove_under_delta_nodes(graph, 10.0);
+        let mut graph = Graph::_from("3758221284␟48.8273411␟2.3486982\n3761637489␟48.8275453␟2.348698\n3761637482␟48.8274512␟2.3486719\n3761637496␟48.8278544␟2.3473522\n2576426847␟48.8273391␟2.3487858\n1829061614␟48.8273732␟2.3487375\n2576426853␟48.8274352␟2.348721\n2576426850␟48.8274242␟2.3486471\n1852590201␟48.8276523␟2.3494784\n3758221292␟48.8274025␟2.3486929\n3761637486␟48.8275249␟2.348704\n2268836829␟48.8276001␟2.3486802\n92192237␟48.8275872␟2.3490245\n1829061602␟48.8275089␟2.3484223\n2576426853␟3761637486\n2576426850␟3758221292\n1829061614␟3758221292\n2268836829␟3761637489\n3761637489␟92192237\n2576426853␟3761637482\n3761637482␟3761637486\n1829061614␟3758221284\n1829061602␟3761637496\n1852590201␟92192237\n2576426850␟3761637482\n1829061602␟2576426850\n1829061614␟2576426847\n1829061602␟3761637489\n2576426853␟3758221292\n3761637486␟3761637489\n1829061614␟92192237", '␟')
+        .unwrap();
+        graph = remove_under_delta_nodes(graph, 10.0, &mut Strategy::random(Some(0))).unwrap();
         assert!(graph.nodes.len() == 9);
         assert!(graph.links.len() / 2 == 12);
         let expected: Vec<(&str, usize)> = vec![
@@ -268,14 +525,16 @@ mod tests {
         ];
         for (node, degree) in expected {
             assert!(graph.nodes.contains_key(node));
-            assert!(graph.get_node(node).neighbours.len() == degree);
+            assert!(graph.get_node(node).unwrap().neighbours.len() == degree);
         }
     }
 
     #[test]
     fn test_replace_link_by_node() {
-        let mut graph = Graph::_from("1852590201␟48.8276523␟2.3494784\n92192237␟48.8275872␟2.3490245\n3761637489␟48.8275453␟2.348698\n1829061602␟48.8275089␟2.3484223\n3761637486␟48.8275249␟2.348704\n1829061614␟48.8273732␟2.3487375\n1852590201␟92192237\n1829061602␟3761637489\n3761637489␟92192237\n3761637486␟3761637489\n1829061614␟92192237", '␟');
-        graph = replace_link_by_node(graph, &("92192237".to_string(), "3761637489".to_string()));
+        let mut graph = Graph::_from("1852590201␟48.8276523␟2.3494784\n92192237␟48.8275872␟2.3490245\n3761637489␟48.8275453␟2.348698\n1829061602␟48.8275089␟2.3484223\n3761637486␟48.8275249␟2.348704\n1829061614␟48.8273732␟2.3487375\n1852590201␟92192237\n1829061602␟3761637489\n3761637489␟92192237\n3761637486␟3761637489\n1829061614␟92192237", '␟')
+        .unwrap();
+        graph = replace_link_by_node(graph, &("92192237".to_string(), "3761637489".to_string()))
+            .unwrap();
         assert!(graph.nodes.len() == 5);
         assert!(graph.links.len() / 2 == 4);
         let expected: Vec<(&str, usize)> = vec![
@@ -287,14 +546,18 @@ mod tests {
         ];
         for (node, degree) in expected {
             assert!(graph.nodes.contains_key(node));
-            assert!(graph.get_node(node).neighbours.len() == degree);
+            assert!(graph.get_node(node).unwrap().neighbours.len() == degree);
         }
+        let graph = Graph::_from("a␟48.8275185␟2.3484976\nb␟48.8275416␟2.3486683\nc␟48.8276001␟2.3486802\na␟b␟a-b␟6\nb␟c␟b-c␟10", '␟').unwrap();
+        let graph = replace_link_by_node(graph, &("a".to_string(), "b".to_string())).unwrap();
+        assert_eq!(graph.length("a-b", "c").unwrap(), 13.0);
     }
 
     #[test]
     fn test_remove_under_delta_links() {
-        let mut graph = Graph::_from("3761637496␟48.8278544␟2.3473522\n3761637486␟48.8275249␟2.348704\n92192237␟48.8275872␟2.3490245\n2576426850␟48.8274242␟2.3486471\n1829061614␟48.8273732␟2.3487375\n1852590201␟48.8276523␟2.3494784\n3761637489␟48.8275453␟2.348698\n2576426853␟48.8274352␟2.348721\n1829061602␟48.8275089␟2.3484223\n3761637489␟92192237\n3761637486␟3761637489\n1829061614␟92192237\n2576426850␟3761637486\n1829061602␟3761637496\n2576426850␟2576426853\n1829061614␟2576426853\n2576426853␟3761637486\n1829061602␟2576426850\n1829061614␟2576426850\n1852590201␟92192237\n1829061602␟3761637489", '␟');
-        graph = remove_under_delta_links(graph, 6.0);
+        let mut graph = Graph::_from("3761637496␟48.8278544␟2.3473522\n3761637486␟48.8275249␟2.348704\n92192237␟48.8275872␟2.3490245\n2576426850␟48.8274242␟2.3486471\n1829061614␟48.8273732␟2.3487375\n1852590201␟48.8276523␟2.3494784\n3761637489␟48.8275453␟2.348698\n2576426853␟48.8274352␟2.348721\n1829061602␟48.8275089␟2.3484223\n3761637489␟92192237\n3761637486␟3761637489\n1829061614␟92192237\n2576426850␟3761637486\n1829061602␟3761637496\n2576426850␟2576426853\n1829061614␟2576426853\n2576426853␟3761637486\n1829061602␟2576426850\n1829061614␟2576426850\n1852590201␟92192237\n1829061602␟3761637489", '␟')
+        .unwrap();
+        graph = remove_under_delta_links(graph, 6.0, &mut Strategy::random(Some(0))).unwrap();
         assert!(graph.nodes.len() == 7);
         assert!(graph.links.len() / 2 == 8);
         let expected: Vec<(&str, usize)> = vec![
@@ -308,7 +571,105 @@ mod tests {
         ];
         for (node, degree) in expected {
             assert!(graph.nodes.contains_key(node));
-            assert!(graph.get_node(node).neighbours.len() == degree);
+            assert!(graph.get_node(node).unwrap().neighbours.len() == degree);
         }
     }
+
+    #[test]
+    fn test_remove_degree_two_nodes_keeps_parallel_edges() {
+        let mut graph = Graph::_from("a␟48.8275185␟2.3484976\nb␟48.8275416␟2.3486683\nc␟48.8276001␟2.3486802\nd␟48.8278544␟2.3473522\nx␟48.8275453␟2.348698\na␟b␟a-b␟10\na␟x␟a-x␟4\nx␟b␟b-x␟8\na␟c␟a-c␟1\nb␟d␟b-d␟1", '␟')
+        .unwrap();
+        graph = remove_degree_two_nodes(graph).unwrap();
+        assert!(!graph.nodes.contains_key("x"));
+        assert!(graph.links.len() / 2 == 3);
+        let mut lengths: Vec<f64> = graph.get_edges("a", "b").iter().map(|e| e.length).collect();
+        lengths.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        assert_eq!(lengths, vec![10.0, 12.0]);
+    }
+
+    #[test]
+    fn test_remove_degree_two_node_with_parallel_streets() {
+        let mut graph = Graph::_from("a␟48.8275185␟2.3484976\nb␟48.8275416␟2.3486683\nx␟48.8275453␟2.348698\na␟x␟e1␟10\na␟x␟e2␟20\nx␟b␟e3␟5", '␟')
+        .unwrap();
+        graph = remove_degree_two_nodes(graph).unwrap();
+        assert!(!graph.nodes.contains_key("x"));
+        let mut lengths: Vec<f64> = graph.get_edges("a", "b").iter().map(|e| e.length).collect();
+        lengths.sort_by(|x, y| x.partial_cmp(y).unwrap());
+        assert_eq!(lengths, vec![15.0, 25.0]);
+    }
+
+    #[test]
+    fn test_strategies_are_reproducible() {
+        let input = "3761637496␟48.8278544␟2.3473522\n3761637486␟48.8275249␟2.348704\n92192237␟48.8275872␟2.3490245\n2576426850␟48.8274242␟2.3486471\n1829061614␟48.8273732␟2.3487375\n1852590201␟48.8276523␟2.3494784\n3761637489␟48.8275453␟2.348698\n2576426853␟48.8274352␟2.348721\n1829061602␟48.8275089␟2.3484223\n3761637489␟92192237\n3761637486␟3761637489\n1829061614␟92192237\n2576426850␟3761637486\n1829061602␟3761637496\n2576426850␟2576426853\n1829061614␟2576426853\n2576426853␟3761637486\n1829061602␟2576426850\n1829061614␟2576426850\n1852590201␟92192237\n1829061602␟3761637489";
+        let run = |mut strategy: Strategy| -> Vec<u8> {
+            let graph = Graph::_from(input, '␟').unwrap();
+            let graph = simplify(graph, 6.0, &mut strategy).unwrap();
+            let mut output: Vec<u8> = Vec::new();
+            graph.write(&mut output, '␟', Order::Insertion).unwrap();
+            output
+        };
+        assert_eq!(
+            run(Strategy::random(Some(42))),
+            run(Strategy::random(Some(42)))
+        );
+        assert_eq!(run(Strategy::Shortest), run(Strategy::Shortest));
+        let shortest = |seed: u64| -> Vec<u8> {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let (mut nodes, mut links): (Vec<&str>, Vec<&str>) =
+                input.lines().partition(|line| line.split('␟').count() == 3);
+            nodes.shuffle(&mut rng);
+            links.shuffle(&mut rng);
+            nodes.append(&mut links);
+            let graph = Graph::_from(&nodes.join("\n"), '␟').unwrap();
+            let graph = simplify(graph, 6.0, &mut Strategy::Shortest).unwrap();
+            let mut output: Vec<u8> = Vec::new();
+            graph.write(&mut output, '␟', Order::Natural).unwrap();
+            output
+        };
+        assert_eq!(shortest(1), shortest(2));
+    }
+
+    #[test]
+    fn test_strategy_kind() {
+        assert_eq!("shortest".parse(), Ok(StrategyKind::Shortest));
+        assert_eq!(StrategyKind::Random.to_string(), "random");
+        assert!("greedy".parse::<StrategyKind>().is_err());
+    }
+
+    #[test]
+    fn test_steps() {
+        assert_eq!(
+            Step::parse_list("largest,deg2,nodes:8,links:5,deg2", None).unwrap(),
+            vec![
+                Step::Largest,
+                Step::DegreeTwo,
+                Step::Nodes(8.0),
+                Step::Links(5.0),
+                Step::DegreeTwo
+            ]
+        );
+        assert_eq!(
+            Step::parse_list("nodes,links:2.5", Some(10.0)).unwrap(),
+            vec![Step::Nodes(10.0), Step::Links(2.5)]
+        );
+        assert!(Step::parse_list("nodes", None).is_err());
+        assert!(Step::parse_list("largest,deg3", None).is_err());
+        assert!(Step::parse_list("links:-1", None).is_err());
+        assert!(Step::parse_list("deg2:4", None).is_err());
+        let steps: Vec<String> = Step::defaults(6.0)
+            .iter()
+            .map(|step| step.to_string())
+            .collect();
+        assert_eq!(steps.join(","), "largest,deg2,nodes:6,links:6");
+        let input = "a␟48.8275185␟2.3484976\nb␟48.8275416␟2.3486683\nc␟48.8276001␟2.3486802\nd␟48.8278544␟2.3473522\na␟b\nb␟c\nc␟d";
+        let graph = Graph::_from(input, '␟').unwrap();
+        let mut strategy = Strategy::Shortest;
+        let graph = simplify_steps(graph, &[Step::Links(20.0)], &mut strategy).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        let graph = Graph::_from(input, '␟').unwrap();
+        let graph =
+            simplify_steps(graph, &[Step::DegreeTwo, Step::Links(20.0)], &mut strategy).unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert!(graph.nodes.contains_key("a") && graph.nodes.contains_key("d"));
+    }
 }
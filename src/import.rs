@@ -0,0 +1,216 @@
+use crate::merge::Grid;
+use crate::{haversine_distance, Edge, Graph, GraphError, Node, Point};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Polyline of longitude latitude points, such as a street
+pub type Line = Vec<Point>;
+
+/// Builds a graph with a node by distinct vertex, named 0..n in order of appearance, and an edge
+/// by segment whose length is the distance between its vertices. Vertices closer than tolerance
+/// meters to a previous one are unified with it, identical ones always are
+pub fn import_lines(lines: &[Line], tolerance: f64) -> Result<Graph, GraphError> {
+    let mut graph = Graph::new();
+    let mut grid = Grid::new(tolerance);
+    let mut identical: HashMap<(u64, u64), String> = HashMap::new();
+    for line in lines {
+        let mut previous: Option<String> = None;
+        for point in line {
+            if !point.x.is_finite() || !point.y.is_finite() {
+                return Err(GraphError::InvalidRecord(format!(
+                    "invalid position {} {}",
+                    point.x, point.y
+                )));
+            }
+            let key = (point.x.to_bits(), point.y.to_bits());
+            let found = match identical.get(&key) {
+                Some(node_id) => Some(node_id.clone()),
+                None => grid.nearest(&graph, point)?,
+            };
+            let node_id = match found {
+                Some(node_id) => node_id,
+                None => {
+                    let node_id = graph.nodes.len().to_string();
+                    graph.insert_node(Node {
+                        id: node_id.clone(),
+                        longitude: point.x.to_string(),
+                        latitude: point.y.to_string(),
+                        neighbours: Vec::new(),
+                    });
+                    grid.insert(&node_id, point);
+                    identical.insert(key, node_id.clone());
+                    node_id
+                }
+            };
+            if let Some(previous) = previous.filter(|previous| *previous != node_id) {
+                let length = haversine_distance(&graph.get_node(&previous)?.point()?, point);
+                let edge = Edge {
+                    id: graph.link_id(&previous, &node_id),
+                    length,
+                };
+                graph.insert_edge(&previous, &node_id, edge)?;
+            }
+            previous = Some(node_id);
+        }
+    }
+    return Ok(graph);
+}
+
+/// Collects the LineString and MultiLineString geometries of a GeoJSON geometry, feature or
+/// feature collection, other geometries are ignored
+pub fn read_geojson_lines(geojson: &str) -> Result<Vec<Line>, GraphError> {
+    let value: Value = serde_json::from_str(geojson)
+        .map_err(|error| GraphError::InvalidRecord(format!("invalid GeoJSON {}", error)))?;
+    let mut lines: Vec<Line> = Vec::new();
+    collect_lines(&value, &mut lines)?;
+    return Ok(lines);
+}
+
+/// Reads one LINESTRING or MULTILINESTRING geometry by line, blank lines are ignored
+pub fn read_wkt_lines(wkt: &str) -> Result<Vec<Line>, GraphError> {
+    let mut lines: Vec<Line> = Vec::new();
+    for (index, row) in wkt.lines().enumerate() {
+        let row = row.trim();
+        if row.is_empty() {
+            continue;
+        }
+        let line_error = |error: GraphError| GraphError::Line {
+            number: index + 1,
+            content: row.to_string(),
+            error: Box::new(error),
+        };
+        let upper = row.to_uppercase();
+        let (multi, body) = if let Some(body) = upper.strip_prefix("MULTILINESTRING") {
+            (true, body)
+        } else if let Some(body) = upper.strip_prefix("LINESTRING") {
+            (false, body)
+        } else {
+            return Err(line_error(GraphError::InvalidRecord(
+                "expected LINESTRING or MULTILINESTRING".to_string(),
+            )));
+        };
+        let body = body.trim();
+        let parts: Vec<&str> = if multi {
+            body.strip_prefix("((")
+                .and_then(|body| body.strip_suffix("))"))
+                .map(|body| {
+                    body.split("),")
+                        .map(|part| part.trim().trim_start_matches('('))
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            body.strip_prefix('(')
+                .and_then(|body| body.strip_suffix(')'))
+                .map(|body| vec![body])
+                .unwrap_or_default()
+        };
+        if parts.is_empty() {
+            return Err(line_error(GraphError::InvalidRecord(
+                "expected parenthesized positions".to_string(),
+            )));
+        }
+        for part in parts {
+            let mut line: Line = Vec::new();
+            for position in part.split(',') {
+                let numbers: Vec<f64> = position
+                    .split_whitespace()
+                    .map(|number| number.parse::<f64>())
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| {
+                        line_error(GraphError::InvalidRecord(format!(
+                            "invalid position {}",
+                            position.trim()
+                        )))
+                    })?;
+                match numbers[..] {
+                    [x, y, ..] => line.push(Point { x, y }),
+                    _ => {
+                        return Err(line_error(GraphError::InvalidRecord(format!(
+                            "invalid position {}",
+                            position.trim()
+                        ))))
+                    }
+                }
+            }
+            lines.push(line);
+        }
+    }
+    return Ok(lines);
+}
+
+fn collect_lines(value: &Value, lines: &mut Vec<Line>) -> Result<(), GraphError> {
+    match value["type"].as_str() {
+        Some("FeatureCollection") => {
+            for feature in value["features"].as_array().into_iter().flatten() {
+                collect_lines(feature, lines)?;
+            }
+        }
+        Some("Feature") => collect_lines(&value["geometry"], lines)?,
+        Some("GeometryCollection") => {
+            for geometry in value["geometries"].as_array().into_iter().flatten() {
+                collect_lines(geometry, lines)?;
+            }
+        }
+        Some("LineString") => lines.push(positions(&value["coordinates"])?),
+        Some("MultiLineString") => {
+            for line in value["coordinates"].as_array().into_iter().flatten() {
+                lines.push(positions(line)?);
+            }
+        }
+        _ => {}
+    }
+    return Ok(());
+}
+
+fn positions(value: &Value) -> Result<Line, GraphError> {
+    let invalid = || GraphError::InvalidRecord(format!("invalid line coordinates {}", value));
+    let mut line: Line = Vec::new();
+    for position in value.as_array().ok_or_else(invalid)? {
+        match (position[0].as_f64(), position[1].as_f64()) {
+            (Some(x), Some(y)) => line.push(Point { x, y }),
+            _ => return Err(invalid()),
+        }
+    }
+    return Ok(line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_import_geojson() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "properties": {"name": "Rue des Cailles"}, "geometry": {"type": "LineString", "coordinates": [[2.3484976, 48.8275185], [2.3486683, 48.8275416], [2.348698, 48.8275453]]}},
+            {"type": "Feature", "properties": {}, "geometry": {"type": "MultiLineString", "coordinates": [[[2.348698, 48.8275453], [2.3486802, 48.8276001]], [[2.3486685, 48.8275417], [2.348704, 48.8275249]]]}},
+            {"type": "Feature", "properties": {}, "geometry": {"type": "Point", "coordinates": [2.3, 48.8]}}
+        ]}"#;
+        let lines = read_geojson_lines(geojson).unwrap();
+        assert_eq!(lines.len(), 3);
+        let graph = import_lines(&lines, 0.0).unwrap();
+        assert_eq!(graph.nodes.len(), 6);
+        assert_eq!(graph.edges_count(), 4);
+        let graph = import_lines(&lines, 1.0).unwrap();
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.get_node("1").unwrap().neighbours.len(), 3);
+        let length = graph.length("0", "1").unwrap();
+        assert!((length - graph.distance("0", "1").unwrap()).abs() < 1e-9);
+        assert!(graph.check_invariants().is_empty());
+        assert!(read_geojson_lines(r#"{"type": "LineString", "coordinates": [[2.3]]}"#).is_err());
+    }
+
+    #[test]
+    fn test_import_wkt() {
+        let wkt = "LINESTRING (2.3484976 48.8275185, 2.3486683 48.8275416, 2.348698 48.8275453)\n\nMULTILINESTRING ((2.348698 48.8275453, 2.3486802 48.8276001), (2.3486683 48.8275416, 2.348704 48.8275249))";
+        let lines = read_wkt_lines(wkt).unwrap();
+        assert_eq!(lines.len(), 3);
+        let graph = import_lines(&lines, 0.0).unwrap();
+        assert_eq!(graph.nodes.len(), 5);
+        assert_eq!(graph.edges_count(), 4);
+        assert!(matches!(
+            read_wkt_lines("POINT (2.3 48.8)"),
+            Err(GraphError::Line { number: 1, .. })
+        ));
+        assert!(read_wkt_lines("LINESTRING (2.3 a)").is_err());
+    }
+}
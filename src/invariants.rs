@@ -0,0 +1,162 @@
+use crate::{determinist, natural_cmp, Graph, Link, Order};
+use std::fmt;
+
+/// Inconsistency found in a graph by check_invariants
+#[derive(Debug, PartialEq)]
+pub enum Violation {
+    UnknownNode(Link),
+    SelfLoop(String),
+    AsymmetricLink(Link),
+    /// The link stores an index which does not lead to its target in the neighbours of its source
+    NeighbourIndex(Link, usize),
+    DuplicateNeighbour(Link),
+    MissingLink(Link),
+    MissingEdges(Link),
+    OrphanEdges(Link),
+    InvalidCoordinates(String),
+    InvalidLength(Link, String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Violation::UnknownNode((u, v)) => write!(f, "link {} {} has an unknown node", u, v),
+            Violation::SelfLoop(u) => write!(f, "node {} is linked to itself", u),
+            Violation::AsymmetricLink((u, v)) => {
+                write!(f, "link {} {} has no reverse link", u, v)
+            }
+            Violation::NeighbourIndex((u, v), index) => write!(
+                f,
+                "link {} {} stores index {} which is not {} in the neighbours of {}",
+                u, v, index, v, u
+            ),
+            Violation::DuplicateNeighbour((u, v)) => {
+                write!(f, "node {} lists neighbour {} more than once", u, v)
+            }
+            Violation::MissingLink((u, v)) => {
+                write!(f, "node {} lists neighbour {} without link", u, v)
+            }
+            Violation::MissingEdges((u, v)) => write!(f, "link {} {} has no edge", u, v),
+            Violation::OrphanEdges((u, v)) => write!(f, "edges {} {} have no link", u, v),
+            Violation::InvalidCoordinates(u) => write!(f, "node {} has invalid coordinates", u),
+            Violation::InvalidLength((u, v), id) => {
+                write!(
+                    f,
+                    "edge {} between {} and {} has an invalid length",
+                    id, u, v
+                )
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// Checks that links are symmetric, that link indexes match neighbours, that every link
+    /// has edges and that coordinates are numbers within range, reporting every violation
+    pub fn check_invariants(&self) -> Vec<Violation> {
+        let mut violations: Vec<Violation> = Vec::new();
+        for node in self.sorted_nodes(Order::Natural) {
+            let latitude = node.latitude.parse::<f64>().unwrap_or(f64::NAN);
+            let longitude = node.longitude.parse::<f64>().unwrap_or(f64::NAN);
+            if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+                violations.push(Violation::InvalidCoordinates(node.id.clone()));
+            }
+            for (index, neighbour_id) in node.neighbours.iter().enumerate() {
+                let link = (node.id.clone(), neighbour_id.clone());
+                match self.links.get(&link) {
+                    None => violations.push(Violation::MissingLink(link)),
+                    Some(stored)
+                        if *stored != index && node.neighbours[..index].contains(neighbour_id) =>
+                    {
+                        violations.push(Violation::DuplicateNeighbour(link))
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let natural = |a: &&Link, b: &&Link| natural_cmp(&a.0, &b.0).then(natural_cmp(&a.1, &b.1));
+        let mut links: Vec<&Link> = self.links.keys().collect();
+        links.sort_by(natural);
+        for link in links {
+            let (u, v) = link;
+            if u == v {
+                violations.push(Violation::SelfLoop(u.clone()));
+            }
+            match (self.nodes.get(u), self.nodes.contains_key(v)) {
+                (Some(source), true) => {
+                    let index = self.links[link];
+                    if source.neighbours.get(index) != Some(v) {
+                        violations.push(Violation::NeighbourIndex(link.clone(), index));
+                    }
+                }
+                _ => violations.push(Violation::UnknownNode(link.clone())),
+            }
+            if !self.contains_link((v, u)) {
+                violations.push(Violation::AsymmetricLink(link.clone()));
+            }
+            if u <= v && !self.edges.contains_key(&determinist(u.clone(), v.clone())) {
+                violations.push(Violation::MissingEdges(link.clone()));
+            }
+        }
+        let mut pairs: Vec<&Link> = self.edges.keys().collect();
+        pairs.sort_by(natural);
+        for pair in pairs {
+            if !self.contains_link((&pair.0, &pair.1)) && !self.contains_link((&pair.1, &pair.0)) {
+                violations.push(Violation::OrphanEdges(pair.clone()));
+            }
+            for edge in &self.edges[pair] {
+                if !(edge.length >= 0.0 && edge.length.is_finite()) {
+                    violations.push(Violation::InvalidLength(pair.clone(), edge.id.clone()));
+                }
+            }
+        }
+        return violations;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_check_invariants() {
+        let graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637486␟48.8275249␟2.348704\n3761637489␟48.8275453␟2.348698\n3761637489␟3761637488\n3761637489␟3761637486", '␟').unwrap();
+        assert_eq!(graph.check_invariants(), vec![]);
+    }
+
+    #[test]
+    fn test_check_invariants_violations() {
+        let mut graph = Graph::_from(
+            "u␟48.8275416␟2.3486683\nv␟48.8275249␟2.348704\nw␟NaN␟2.348698\nu␟v\nv␟w\nw␟w",
+            '␟',
+        )
+        .unwrap();
+        graph.links.remove(&("v".to_string(), "u".to_string()));
+        graph.links.insert(("v".to_string(), "w".to_string()), 3);
+        graph
+            .nodes
+            .get_mut("u")
+            .unwrap()
+            .neighbours
+            .push("v".to_string());
+        graph
+            .edges
+            .get_mut(&("u".to_string(), "v".to_string()))
+            .unwrap()[0]
+            .length = -1.0;
+        let link = |u: &str, v: &str| (u.to_string(), v.to_string());
+        assert_eq!(
+            graph.check_invariants(),
+            vec![
+                Violation::DuplicateNeighbour(link("u", "v")),
+                Violation::MissingLink(link("v", "u")),
+                Violation::InvalidCoordinates("w".to_string()),
+                Violation::AsymmetricLink(link("u", "v")),
+                Violation::NeighbourIndex(link("v", "w"), 3),
+                Violation::SelfLoop("w".to_string()),
+                Violation::InvalidLength(link("u", "v"), "u-v".to_string()),
+                Violation::InvalidLength(link("v", "w"), "v-w".to_string()),
+                Violation::InvalidLength(link("w", "w"), "w-w".to_string()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,133 @@
+use crate::gpx::route_nodes;
+use crate::{escape_xml, Graph, GraphError, Node, Order};
+use std::io::Write;
+
+/// Writes a KML document for Google Earth: a folder of node Placemarks named after their id and
+/// a folder of link LineStrings named after their id and described by their length
+pub fn write_kml(graph: &Graph, writer: &mut impl Write, order: Order) -> Result<(), GraphError> {
+    write_start(writer, &graph.header)?;
+    writeln!(writer, "    <Folder>")?;
+    writeln!(writer, "      <name>nodes</name>")?;
+    for node in graph.sorted_nodes(order) {
+        writeln!(writer, "      <Placemark>")?;
+        writeln!(writer, "        <name>{}</name>", escape_xml(&node.id))?;
+        writeln!(
+            writer,
+            "        <Point><coordinates>{}</coordinates></Point>",
+            coordinates(&[node])
+        )?;
+        writeln!(writer, "      </Placemark>")?;
+    }
+    writeln!(writer, "    </Folder>")?;
+    writeln!(writer, "    <Folder>")?;
+    writeln!(writer, "      <name>links</name>")?;
+    for (source, target) in graph.sorted_pairs(order) {
+        let nodes = [&graph.nodes[source], &graph.nodes[target]];
+        for edge in graph.sorted_edges(source, target, order) {
+            write_line(writer, &edge.id, edge.length, &nodes)?;
+        }
+    }
+    writeln!(writer, "    </Folder>")?;
+    write_end(writer)?;
+    return Ok(());
+}
+
+/// Writes a KML document with a single LineString following the route, a sequence of linked
+/// node ids, described by its length
+pub fn write_kml_route(
+    graph: &Graph,
+    route: &[&str],
+    writer: &mut impl Write,
+) -> Result<(), GraphError> {
+    let nodes = route_nodes(graph, route)?;
+    let mut length = 0.0;
+    for step in route.windows(2) {
+        length += graph.length(step[0], step[1])?;
+    }
+    write_start(writer, &graph.header)?;
+    write_line(writer, "route", length, &nodes)?;
+    write_end(writer)?;
+    return Ok(());
+}
+
+/// Longitude,latitude tuples separated by spaces
+fn coordinates(nodes: &[&Node]) -> String {
+    return nodes
+        .iter()
+        .map(|node| {
+            format!(
+                "{},{}",
+                escape_xml(&node.longitude),
+                escape_xml(&node.latitude)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+}
+
+fn write_line(
+    writer: &mut impl Write,
+    name: &str,
+    length: f64,
+    nodes: &[&Node],
+) -> Result<(), GraphError> {
+    writeln!(writer, "      <Placemark>")?;
+    writeln!(writer, "        <name>{}</name>", escape_xml(name))?;
+    writeln!(writer, "        <description>{} m</description>", length)?;
+    writeln!(
+        writer,
+        "        <LineString><coordinates>{}</coordinates></LineString>",
+        coordinates(nodes)
+    )?;
+    writeln!(writer, "      </Placemark>")?;
+    return Ok(());
+}
+
+fn write_start(writer: &mut impl Write, header: &[String]) -> Result<(), GraphError> {
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(writer, "<kml xmlns=\"http://www.opengis.net/kml/2.2\">")?;
+    writeln!(writer, "  <Document>")?;
+    writeln!(writer, "    <name>ophois</name>")?;
+    if !header.is_empty() {
+        writeln!(
+            writer,
+            "    <description>{}</description>",
+            escape_xml(&header.join("\n"))
+        )?;
+    }
+    return Ok(());
+}
+
+fn write_end(writer: &mut impl Write) -> Result<(), GraphError> {
+    writeln!(writer, "  </Document>")?;
+    writeln!(writer, "</kml>")?;
+    writer.flush()?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_write_kml() {
+        let graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637486␟48.8275249␟2.348704\n3761637489␟3761637488␟3761637488-3761637489␟2.5\n3761637489␟3761637488␟a&b␟3\n3761637489␟3761637486␟3761637486-3761637489␟12.7", '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_kml(&graph, &mut output, Order::Natural).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("<Point>").count(), 3);
+        assert_eq!(output.matches("<LineString>").count(), 3);
+        assert!(output.contains("<name>3761637486</name>\n        <Point><coordinates>2.348704,48.8275249</coordinates></Point>"));
+        assert!(output.contains("<name>a&amp;b</name>\n        <description>3 m</description>\n        <LineString><coordinates>2.3486683,48.8275416 2.348698,48.8275453</coordinates></LineString>"));
+        assert!(output.ends_with("</Document>\n</kml>\n"));
+        let mut output: Vec<u8> = Vec::new();
+        write_kml_route(
+            &graph,
+            &["3761637488", "3761637489", "3761637486"],
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("<name>route</name>\n        <description>15.2 m</description>"));
+        assert!(write_kml_route(&graph, &["3761637488", "3761637486"], &mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,78 @@
+//! Ophoïs, the opener of the ways, creates streets graph from OpenStreetMap
+//!
+//! Graphs are loaded from the text or binary format, simplified, discretized and measured:
+//!
+//! ```
+//! use ophois::{degrees, discretize, haversine_distance, simplify, Graph, Order, Strategy};
+//!
+//! let input = "1␟48.8275185␟2.3484976\n2␟48.8275416␟2.3486683\n3␟48.8278544␟2.3473522\n1␟2\n2␟3";
+//! let mut graph = Graph::read(input.as_bytes(), '␟')?;
+//! let length = haversine_distance(
+//!     &graph.get_node("2")?.point()?,
+//!     &graph.get_node("3")?.point()?,
+//! );
+//! assert!(length > 100.0);
+//! graph = simplify(graph, 10.0, &mut Strategy::random(Some(42)))?;
+//! graph = discretize(graph, 5.0)?;
+//! assert_eq!(degrees(&graph)[&1], 2);
+//! let mut output: Vec<u8> = Vec::new();
+//! graph.write(&mut output, '␟', Order::Natural)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+#![allow(clippy::needless_return)]
+#![allow(clippy::bool_comparison, clippy::bool_assert_comparison)]
+pub mod binary;
+pub mod clip;
+pub mod diff;
+pub mod discretize;
+pub mod dot;
+pub mod error;
+pub mod formats;
+pub mod geo;
+pub mod geojson;
+pub mod gpx;
+pub mod graph;
+pub mod graphml;
+pub mod heuristics;
+pub mod import;
+pub mod invariants;
+pub mod kml;
+pub mod merge;
+pub mod metis;
+pub mod metrics;
+pub mod openstreetmap;
+pub mod origins;
+pub mod osm;
+pub mod overpass;
+pub mod render;
+pub mod renumber;
+pub mod tables;
+pub mod utils;
+
+pub use binary::*;
+pub use clip::*;
+pub use diff::*;
+pub use discretize::*;
+pub use dot::*;
+pub use error::*;
+pub use formats::*;
+pub use geo::*;
+pub use geojson::*;
+pub use gpx::*;
+pub use graph::*;
+pub use graphml::*;
+pub use heuristics::*;
+pub use import::*;
+pub use invariants::*;
+pub use kml::*;
+pub use merge::*;
+pub use metis::*;
+pub use metrics::*;
+pub use openstreetmap::*;
+pub use origins::*;
+pub use osm::*;
+pub use overpass::*;
+pub use render::*;
+pub use renumber::*;
+pub use tables::*;
+pub use utils::*;
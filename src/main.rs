@@ -1,23 +1,11 @@
-mod discretize;
-mod geo;
-mod graph;
-mod heuristics;
-mod metrics;
-mod openstreetmap;
-mod overpass;
-mod utils;
-
-use clap::Parser;
-use discretize::*;
-use geo::*;
-use graph::*;
-use heuristics::*;
-use metrics::*;
-use openstreetmap::*;
-use overpass::*;
+#![allow(clippy::needless_return)]
+use clap::{ArgGroup, Parser};
+use ophois::*;
+use std::fs::File;
 use std::io;
 use std::io::prelude::*;
-use utils::*;
+use std::path::Path;
+use std::process;
 
 #[derive(Parser)]
 #[clap(author, about, version, bin_name = "ophois")]
@@ -30,19 +18,65 @@ enum Ophois {
         #[clap(short, long, default_value = "(way(area)[highway]; ); (._;>;);")]
         overpassql: String,
     },
-    Format,
+    Format {
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
+    },
     Extract {
         /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
         #[clap(short, long, default_value_t = '␟')]
         separator: char,
+        /// Name of the extracted city/area, recorded in the graph header
+        #[clap(short, long)]
+        area: Option<String>,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
     },
     Simplify {
         /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
         #[clap(short, long, default_value_t = '␟')]
         separator: char,
         /// Delta is expressed in meters
+        #[clap(short, long, required_unless_present = "steps")]
+        delta: Option<f64>,
+        /// Comma separated steps run in order instead of largest,deg2,nodes,links: largest (component), deg2 (degree two nodes), nodes:delta or links:delta (under delta nodes or links), nodes and links alone use --delta
+        #[clap(long)]
+        steps: Option<String>,
+        /// Sidecar file mapping created node ids to their original nodes, read first if it exists
         #[clap(short, long)]
-        delta: f64,
+        provenance: Option<String>,
+        /// Order of nodes and links in output: natural (ids) or insertion
+        #[clap(short, long, default_value = "natural")]
+        order: Order,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
+        /// Format of the input graph: text, bin, geojson or graphml, guessed from the input extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+        /// Format of the output graph: text, bin, geojson, graphml, osm, dot, metis, metis-weighted, mtx, gpx or kml, guessed from the output extension by default
+        #[clap(long)]
+        output_format: Option<GraphFormat>,
+        /// Directory where order and size, degrees and lengths distributions are written
+        #[clap(long)]
+        metrics: Option<String>,
+        /// Order of under delta nodes and links: random or shortest (links first)
+        #[clap(long, default_value = "random", possible_values = &["random", "shortest"])]
+        strategy: StrategyKind,
+        /// Seed of the random strategy, to reproduce a simplification
+        #[clap(long)]
+        seed: Option<u64>,
     },
     Discretize {
         /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
@@ -51,32 +85,800 @@ enum Ophois {
         /// Delta is expressed in meters
         #[clap(short, long)]
         delta: f64,
+        /// Sidecar file mapping created node ids to their original nodes, read first if it exists
+        #[clap(short, long)]
+        provenance: Option<String>,
+        /// Order of nodes and links in output: natural (ids) or insertion
+        #[clap(short, long, default_value = "natural")]
+        order: Order,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
+        /// Format of the input graph: text, bin, geojson or graphml, guessed from the input extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+        /// Format of the output graph: text, bin, geojson, graphml, osm, dot, metis, metis-weighted, mtx, gpx or kml, guessed from the output extension by default
+        #[clap(long)]
+        output_format: Option<GraphFormat>,
+        /// Directory where order and size, degrees and lengths distributions are written
+        #[clap(long)]
+        metrics: Option<String>,
+    },
+    /// Report every faulty record and inconsistency of a graph
+    Validate {
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Format of the input graph: text, bin, geojson or graphml, guessed from the input extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+    },
+    /// Report added, removed and moved nodes and added and removed links from old to new
+    Diff {
+        /// Old graph file
+        old: String,
+        /// New graph file
+        new: String,
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// Format of both input graphs: text, bin, geojson or graphml, guessed from their extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+        /// Nodes which moved by less than tolerance meters are not reported
+        #[clap(short, long, default_value_t = 0.0)]
+        tolerance: f64,
+        /// Print one separated record by change without summary
+        #[clap(short, long)]
+        machine: bool,
+    },
+    /// Union graphs, nodes sharing an id are unified and nodes closer than tolerance are snapped together
+    Merge {
+        /// Graph files, nodes of a graph are snapped onto the nodes of the previous ones
+        #[clap(required = true, min_values = 2)]
+        graphs: Vec<String>,
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// Nodes of different graphs closer than tolerance meters are merged into one
+        #[clap(short, long, default_value_t = 0.0)]
+        tolerance: f64,
+        /// Keep only the largest component if the merged graph is not connected, otherwise a disconnected merge fails
+        #[clap(short, long)]
+        largest: bool,
+        /// Sidecar file mapping created node ids to their original nodes, read first if it exists
+        #[clap(short, long)]
+        provenance: Option<String>,
+        /// Order of nodes and links in output: natural (ids) or insertion
+        #[clap(short, long, default_value = "natural")]
+        order: Order,
+        /// Format of the input graphs: text, bin, geojson or graphml, guessed from their extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
+        /// Format of the output graph: text, bin, geojson, graphml, osm, dot, metis, metis-weighted, mtx, gpx or kml, guessed from the output extension by default
+        #[clap(long)]
+        output_format: Option<GraphFormat>,
+    },
+    /// Keep the part of a graph within a bounding box, polygons or a circle
+    #[clap(group(ArgGroup::new("area").required(true).args(&["bbox", "polygon", "around"])))]
+    Clip {
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// Bounding box: south,west,north,east
+        #[clap(long, allow_hyphen_values = true)]
+        bbox: Option<String>,
+        /// GeoJSON file holding Polygon or MultiPolygon geometries
+        #[clap(long)]
+        polygon: Option<String>,
+        /// Circle: latitude,longitude,radius with radius in meters
+        #[clap(long, allow_hyphen_values = true)]
+        around: Option<String>,
+        /// Links crossing the boundary are dropped or cut at new boundary nodes, also links through the area with both nodes outside
+        #[clap(short, long, default_value = "drop", possible_values = &["drop", "cut"])]
+        boundary: Boundary,
+        /// Sidecar file mapping created node ids to their original nodes, read first if it exists
+        #[clap(short, long)]
+        provenance: Option<String>,
+        /// Order of nodes and links in output: natural (ids) or insertion
+        #[clap(short, long, default_value = "natural")]
+        order: Order,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
+        /// Format of the input graph: text, bin, geojson or graphml, guessed from the input extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+        /// Format of the output graph: text, bin, geojson, graphml, osm, dot, metis, metis-weighted, mtx, gpx or kml, guessed from the output extension by default
+        #[clap(long)]
+        output_format: Option<GraphFormat>,
+    },
+    /// Rename nodes 0..n, in natural id order or in an order keeping close nodes close
+    Renumber {
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// Order of new ids: natural, hilbert (curve over coordinates) or bfs
+        #[clap(short, long, default_value = "hilbert", possible_values = &["natural", "hilbert", "bfs"])]
+        numbering: Numbering,
+        /// File where each line maps an old id to its new id
+        #[clap(short, long)]
+        mapping: Option<String>,
+        /// Sidecar file mapping created node ids to their original nodes, read first if it exists
+        #[clap(short, long)]
+        provenance: Option<String>,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
+        /// Format of the input graph: text, bin, geojson or graphml, guessed from the input extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+        /// Format of the output graph: text, bin, geojson, graphml, osm, dot, metis, metis-weighted, mtx, gpx or kml, guessed from the output extension by default
+        #[clap(long)]
+        output_format: Option<GraphFormat>,
+    },
+    /// Build a graph from the lines of a GeoJSON or WKT file instead of OpenStreetMap
+    #[clap(group(ArgGroup::new("source").required(true).args(&["geojson", "wkt"])))]
+    Import {
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// GeoJSON file holding LineString or MultiLineString geometries
+        #[clap(long)]
+        geojson: Option<String>,
+        /// WKT file holding one LINESTRING or MULTILINESTRING by line
+        #[clap(long)]
+        wkt: Option<String>,
+        /// Vertices closer than tolerance meters are unified into one node
+        #[clap(short, long, default_value_t = 0.0)]
+        tolerance: f64,
+        /// Order of nodes and links in output: natural (ids) or insertion
+        #[clap(short, long, default_value = "natural")]
+        order: Order,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
+        /// Format of the output graph: text, bin, geojson, graphml, osm, dot, metis, metis-weighted, mtx, gpx or kml, guessed from the output extension by default
+        #[clap(long)]
+        output_format: Option<GraphFormat>,
+    },
+    /// Draw a graph in SVG, optionally over the graph it was simplified from
+    Render {
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// SVG file to write, the standard output by default
+        #[clap(long)]
+        svg: Option<String>,
+        /// Graph file drawn in grey under the graph, such as the graph before simplification
+        #[clap(long)]
+        overlay: Option<String>,
+        /// Colour of nodes (and links by component): uniform, degree or component, not by tag as graphs keep no OpenStreetMap tags
+        #[clap(short, long, default_value = "uniform", possible_values = &["uniform", "degree", "component"])]
+        colouring: Colouring,
+        /// Width of the picture in pixels
+        #[clap(short, long, default_value_t = 1000.0)]
+        width: f64,
+        /// Radius of nodes in pixels
+        #[clap(long, default_value_t = 2.0)]
+        node_radius: f64,
+        /// Width of links in pixels
+        #[clap(long, default_value_t = 1.0)]
+        link_width: f64,
+        /// Draw links from half to twice link width, from the shortest to the longest
+        #[clap(long)]
+        width_by_length: bool,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Format of the input graphs: text, bin, geojson or graphml, guessed from their extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+    },
+    /// Write a graph as a nodes table and an edges table with headers, for pandas, R or spreadsheets
+    Tables {
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// Directory where nodes.csv and edges.csv are written
+        #[clap(short, long, default_value = ".")]
+        directory: String,
+        /// Write tab separated nodes.tsv and edges.tsv instead
+        #[clap(long)]
+        tsv: bool,
+        /// Order of nodes and links in output: natural (ids) or insertion
+        #[clap(short, long, default_value = "natural")]
+        order: Order,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Format of the input graph: text, bin, geojson or graphml, guessed from the input extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+    },
+    /// Export a route over a graph, a file of linked node ids one by line, in GPX or KML
+    Route {
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// File listing the node ids of the route in order, one by line
+        #[clap(short, long)]
+        route: String,
+        /// Output file, - for the standard output
+        #[clap(long, default_value = "-")]
+        output: String,
+        /// Format of the route: gpx or kml, guessed from the output extension, gpx by default
+        #[clap(long, possible_values = &["gpx", "kml"])]
+        output_format: Option<GraphFormat>,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Format of the input graph: text, bin, geojson or graphml, guessed from the input extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
+    },
+    /// Print the header and the size of a graph
+    Info {
+        /// Specify a custom separator such as space: -s ' '. Beware that data already contains: [.-:/]
+        #[clap(short, long, default_value_t = '␟')]
+        separator: char,
+        /// Input file, - for the standard input
+        #[clap(long, default_value = "-")]
+        input: String,
+        /// Format of the input graph: text, bin, geojson or graphml, guessed from the input extension by default
+        #[clap(long)]
+        input_format: Option<GraphFormat>,
     },
 }
 
 fn main() {
     match Ophois::parse() {
         Ophois::Download { city, overpassql } => download_map(city, overpassql).unwrap(),
-        Ophois::Format => format_xml(),
-        Ophois::Extract { separator } => {
-            for line in io::stdin().lock().lines() {
-                extract(line.unwrap(), separator);
+        Ophois::Format { input, output } => {
+            let mut writer = create(&output);
+            or_exit_from(
+                &input,
+                write_formatted_xml(open(&input), &mut writer).map_err(GraphError::from),
+            );
+        }
+        Ophois::Extract {
+            separator,
+            area,
+            input,
+            output,
+        } => {
+            let mut command = format!("extract separator={:?}", separator);
+            if let Some(area) = area {
+                command.push_str(&format!(" area={:?}", area));
+            }
+            let mut writer = create(&output);
+            let result = writeln!(writer, "# {}", provenance(&command)).and_then(|_| {
+                for line in open(&input).lines() {
+                    write_extracted(line?, separator, &mut writer)?;
+                }
+                return writer.flush();
+            });
+            or_exit_from(&input, result.map_err(GraphError::from));
+        }
+        Ophois::Simplify {
+            separator,
+            delta,
+            steps,
+            order,
+            strategy,
+            seed,
+            provenance,
+            input,
+            output,
+            input_format,
+            output_format,
+            metrics,
+        } => {
+            let mut command = format!("simplify separator={:?}", separator);
+            let (steps, name) = match (steps, delta) {
+                (Some(steps), _) => {
+                    let steps = or_exit_from(
+                        "steps",
+                        Step::parse_list(&steps, delta).map_err(GraphError::InvalidRecord),
+                    );
+                    let name = steps
+                        .iter()
+                        .map(|step| step.to_string())
+                        .collect::<Vec<String>>()
+                        .join(",");
+                    command.push_str(&format!(" steps={}", name));
+                    (steps, name)
+                }
+                (None, Some(delta)) => {
+                    command.push_str(&format!(" delta={}", delta));
+                    (Step::defaults(delta), delta.to_string())
+                }
+                (None, None) => unreachable!("clap requires delta without steps"),
+            };
+            command.push_str(&format!(" strategy={}", strategy));
+            let mut strategy = match strategy {
+                StrategyKind::Random => {
+                    let seed = seed.unwrap_or_else(rand::random);
+                    command.push_str(&format!(" seed={}", seed));
+                    Strategy::random(Some(seed))
+                }
+                StrategyKind::Shortest => Strategy::Shortest,
+            };
+            let mut graph = load_path(&input, separator, input_format);
+            read_origins(&mut graph, &provenance, separator);
+            graph.annotate(&command);
+            graph = or_exit_from(&input, simplify_steps(graph, &steps, &mut strategy));
+            write_metrics(&graph, format!("simplify={}", name), &metrics);
+            write_origins(&graph, &provenance, separator);
+            save(&graph, &output, separator, order, output_format);
+        }
+        Ophois::Discretize {
+            separator,
+            delta,
+            provenance,
+            order,
+            input,
+            output,
+            input_format,
+            output_format,
+            metrics,
+        } => {
+            let mut graph = load_path(&input, separator, input_format);
+            read_origins(&mut graph, &provenance, separator);
+            graph.annotate(&format!(
+                "discretize separator={:?} delta={}",
+                separator, delta
+            ));
+            graph = or_exit_from(&input, discretize(graph, delta));
+            write_metrics(&graph, format!("discretize={}", delta), &metrics);
+            write_origins(&graph, &provenance, separator);
+            save(&graph, &output, separator, order, output_format);
+        }
+        Ophois::Validate {
+            separator,
+            input,
+            input_format,
+        } => {
+            let (graph, errors) = or_exit_from(
+                &input,
+                match guess_format(&input, input_format) {
+                    GraphFormat::Text => Graph::read_lenient(open(&input), separator),
+                    format => {
+                        read_graph(open(&input), format, separator).map(|graph| (graph, vec![]))
+                    }
+                },
+            );
+            for error in &errors {
+                println!("{}", error);
+            }
+            let violations = graph.check_invariants();
+            for violation in &violations {
+                println!("{}", violation);
+            }
+            if errors.is_empty() && violations.is_empty() {
+                println!("valid graph");
+            } else {
+                println!(
+                    "{} faulty records {} violations",
+                    errors.len(),
+                    violations.len()
+                );
+                process::exit(1);
+            }
+        }
+        Ophois::Diff {
+            old,
+            new,
+            separator,
+            input_format,
+            tolerance,
+            machine,
+        } => {
+            let old_graph = load_file(&old, separator, input_format);
+            let new_graph = load_file(&new, separator, input_format);
+            let graph_diff = or_exit_from(&new, diff(&old_graph, &new_graph, tolerance));
+            for record in graph_diff.records(if machine { separator } else { ' ' }) {
+                println!("{}", record);
+            }
+            if !machine {
+                println!("{}", graph_diff.summary());
+            }
+        }
+        Ophois::Merge {
+            graphs,
+            separator,
+            tolerance,
+            largest,
+            provenance,
+            order,
+            output,
+            input_format,
+            output_format,
+        } => {
+            let command = format!(
+                "merge separator={:?} tolerance={} {}",
+                separator,
+                tolerance,
+                graphs.join(" ")
+            );
+            let inputs = graphs
+                .iter()
+                .map(|path| {
+                    let mut graph = load_file(path, separator, input_format);
+                    read_origins(&mut graph, &provenance, separator);
+                    graph
+                })
+                .collect();
+            let mut graph = or_exit_from("merge", merge(inputs, tolerance));
+            graph.annotate(&command);
+            let violations = graph.check_invariants();
+            if !violations.is_empty() {
+                for violation in &violations {
+                    eprintln!("merge: {}", violation);
+                }
+                process::exit(1);
+            }
+            let components = components_count(&graph);
+            if components > 1 {
+                if largest {
+                    graph = or_exit_from("merge", bfs_largest_component(graph));
+                } else {
+                    eprintln!(
+                        "merge: {} components, use --largest to keep the largest one",
+                        components
+                    );
+                    process::exit(1);
+                }
+            }
+            write_origins(&graph, &provenance, separator);
+            save(&graph, &output, separator, order, output_format);
+        }
+        Ophois::Clip {
+            separator,
+            bbox,
+            polygon,
+            around,
+            boundary,
+            provenance,
+            order,
+            input,
+            output,
+            input_format,
+            output_format,
+        } => {
+            let (area, command) = if let Some(bbox) = bbox {
+                (Area::from_bbox(&bbox), format!("bbox={}", bbox))
+            } else if let Some(around) = around {
+                (Area::from_around(&around), format!("around={}", around))
+            } else {
+                let path = polygon.unwrap();
+                let geojson = or_exit_from(
+                    &path,
+                    std::fs::read_to_string(&path).map_err(GraphError::from),
+                );
+                (Area::from_geojson(&geojson), format!("polygon={:?}", path))
+            };
+            let area = or_exit_from("clip", area);
+            let mut graph = load_path(&input, separator, input_format);
+            read_origins(&mut graph, &provenance, separator);
+            let name = match boundary {
+                Boundary::Drop => "drop",
+                Boundary::Cut => "cut",
+            };
+            graph.annotate(&format!(
+                "clip separator={:?} {} boundary={}",
+                separator, command, name
+            ));
+            graph = or_exit_from(&input, clip(graph, &area, boundary));
+            write_origins(&graph, &provenance, separator);
+            save(&graph, &output, separator, order, output_format);
+        }
+        Ophois::Renumber {
+            separator,
+            numbering,
+            mapping,
+            provenance,
+            input,
+            output,
+            input_format,
+            output_format,
+        } => {
+            let mut graph = load_path(&input, separator, input_format);
+            read_origins(&mut graph, &provenance, separator);
+            let name = match numbering {
+                Numbering::Natural => "natural",
+                Numbering::Hilbert => "hilbert",
+                Numbering::Bfs => "bfs",
+            };
+            graph.annotate(&format!(
+                "renumber separator={:?} numbering={}",
+                separator, name
+            ));
+            let (graph, ids) = or_exit_from(&input, renumber(graph, numbering));
+            if let Some(path) = mapping {
+                let mut output = io::BufWriter::new(or_exit_from(
+                    &path,
+                    File::create(&path).map_err(GraphError::from),
+                ));
+                for (old, new) in ids {
+                    or_exit_from(
+                        &path,
+                        writeln!(output, "{}", join_record(&[&old, &new], separator))
+                            .map_err(GraphError::from),
+                    );
+                }
+                or_exit_from(&path, output.flush().map_err(GraphError::from));
             }
+            write_origins(&graph, &provenance, separator);
+            save(&graph, &output, separator, Order::Natural, output_format);
         }
-        Ophois::Simplify { separator, delta } => {
-            let mut graph = Graph::load(separator);
-            graph = bfs_largest_component(graph);
-            graph = remove_degree_two_nodes(graph);
-            graph = remove_under_delta_nodes(graph, delta);
-            graph = remove_under_delta_links(graph, delta);
-            metrics(&graph, format!("simplify={}", delta));
-            graph.show(separator);
-        }
-        Ophois::Discretize { separator, delta } => {
-            let mut graph = Graph::load(separator);
-            graph = discretize(graph, delta);
-            metrics(&graph, format!("discretize={}", delta));
-            graph.show(separator);
+        Ophois::Import {
+            separator,
+            geojson,
+            wkt,
+            tolerance,
+            order,
+            output,
+            output_format,
+        } => {
+            let path = geojson.clone().or(wkt).unwrap();
+            let content = or_exit_from(
+                &path,
+                std::fs::read_to_string(&path).map_err(GraphError::from),
+            );
+            let lines = or_exit_from(
+                &path,
+                match geojson {
+                    Some(_) => read_geojson_lines(&content),
+                    None => read_wkt_lines(&content),
+                },
+            );
+            let mut graph = or_exit_from(&path, import_lines(&lines, tolerance));
+            graph.annotate(&format!(
+                "import separator={:?} source={:?} tolerance={}",
+                separator, path, tolerance
+            ));
+            save(&graph, &output, separator, order, output_format);
+        }
+        Ophois::Render {
+            separator,
+            svg,
+            overlay,
+            colouring,
+            width,
+            node_radius,
+            link_width,
+            width_by_length,
+            input,
+            input_format,
+        } => {
+            let graph = load_path(&input, separator, input_format);
+            let overlay = overlay.map(|path| load_file(&path, separator, input_format));
+            let style = Style {
+                width,
+                colouring,
+                node_radius,
+                link_width,
+                width_by_length,
+            };
+            match svg {
+                Some(path) => {
+                    let mut output = io::BufWriter::new(or_exit_from(
+                        &path,
+                        File::create(&path).map_err(GraphError::from),
+                    ));
+                    or_exit_from(
+                        &path,
+                        write_svg(&graph, overlay.as_ref(), &style, &mut output),
+                    );
+                }
+                None => or_exit(write_svg(
+                    &graph,
+                    overlay.as_ref(),
+                    &style,
+                    &mut io::BufWriter::new(io::stdout().lock()),
+                )),
+            }
+        }
+        Ophois::Tables {
+            separator,
+            directory,
+            tsv,
+            order,
+            input,
+            input_format,
+        } => {
+            let graph = load_path(&input, separator, input_format);
+            let (delimiter, extension) = if tsv { ('\t', "tsv") } else { (',', "csv") };
+            let nodes = Path::new(&directory).join(format!("nodes.{}", extension));
+            let edges = Path::new(&directory).join(format!("edges.{}", extension));
+            let path = nodes.display().to_string();
+            let mut output = io::BufWriter::new(or_exit_from(
+                &path,
+                File::create(&nodes).map_err(GraphError::from),
+            ));
+            or_exit_from(
+                &path,
+                write_nodes_table(&graph, &mut output, order, delimiter),
+            );
+            let path = edges.display().to_string();
+            let mut output = io::BufWriter::new(or_exit_from(
+                &path,
+                File::create(&edges).map_err(GraphError::from),
+            ));
+            or_exit_from(
+                &path,
+                write_edges_table(&graph, &mut output, order, delimiter),
+            );
+        }
+        Ophois::Route {
+            separator,
+            route,
+            output,
+            output_format,
+            input,
+            input_format,
+        } => {
+            let graph = load_path(&input, separator, input_format);
+            let content = or_exit_from(
+                &route,
+                std::fs::read_to_string(&route).map_err(GraphError::from),
+            );
+            let nodes: Vec<&str> = content
+                .lines()
+                .map(|line| line.trim())
+                .filter(|line| !line.is_empty())
+                .collect();
+            let format = output_format.or_else(|| GraphFormat::from_path(&output));
+            let mut writer = create(&output);
+            or_exit_from(
+                &route,
+                match format {
+                    Some(GraphFormat::Kml) => write_kml_route(&graph, &nodes, &mut writer),
+                    _ => write_gpx_route(&graph, &nodes, &mut writer),
+                },
+            );
+        }
+        Ophois::Info {
+            separator,
+            input,
+            input_format,
+        } => {
+            let graph = load_path(&input, separator, input_format);
+            for line in &graph.header {
+                println!("{}", line);
+            }
+            println!("{} nodes {} links", graph.nodes.len(), graph.edges_count());
+        }
+    }
+}
+
+fn load(separator: char, format: GraphFormat) -> Graph {
+    return or_exit(read_graph(io::stdin().lock(), format, separator));
+}
+
+/// Reads the standard input for -, a file otherwise
+fn load_path(path: &str, separator: char, format: Option<GraphFormat>) -> Graph {
+    return match path {
+        "-" => load(separator, format.unwrap_or(GraphFormat::Text)),
+        path => load_file(path, separator, format),
+    };
+}
+
+/// Without a format, it is guessed from the extension of the file, text by default
+fn guess_format(path: &str, format: Option<GraphFormat>) -> GraphFormat {
+    return format
+        .or_else(|| GraphFormat::from_path(path))
+        .unwrap_or(GraphFormat::Text);
+}
+
+fn load_file(path: &str, separator: char, format: Option<GraphFormat>) -> Graph {
+    let format = guess_format(path, format);
+    let input = or_exit_from(path, File::open(path).map_err(GraphError::from));
+    return or_exit_from(path, read_graph(input, format, separator));
+}
+
+/// Origins carry over from a command to the next through the same sidecar file
+fn read_origins(graph: &mut Graph, path: &Option<String>, separator: char) {
+    if let Some(path) = path {
+        if Path::new(path).exists() {
+            let input = io::BufReader::new(or_exit_from(
+                path,
+                File::open(path).map_err(GraphError::from),
+            ));
+            or_exit_from(path, graph.read_origins(input, separator));
         }
     }
 }
+
+fn write_origins(graph: &Graph, path: &Option<String>, separator: char) {
+    if let Some(path) = path {
+        let mut output = io::BufWriter::new(or_exit_from(
+            path,
+            File::create(path).map_err(GraphError::from),
+        ));
+        or_exit_from(
+            path,
+            graph
+                .write_origins(&mut output, separator)
+                .map_err(GraphError::from),
+        );
+    }
+}
+
+fn show(graph: &Graph, separator: char, order: Order, format: GraphFormat) {
+    or_exit(write_graph(
+        graph,
+        io::stdout().lock(),
+        format,
+        separator,
+        order,
+    ));
+}
+
+/// Writes to the standard output for -, to a file in the format of its extension by default otherwise
+fn save(graph: &Graph, path: &str, separator: char, order: Order, format: Option<GraphFormat>) {
+    if path == "-" {
+        return show(graph, separator, order, format.unwrap_or(GraphFormat::Text));
+    }
+    let format = guess_format(path, format);
+    let output = or_exit_from(path, File::create(path).map_err(GraphError::from));
+    or_exit_from(path, write_graph(graph, output, format, separator, order));
+}
+
+/// Standard input for -
+fn open(path: &str) -> Box<dyn BufRead> {
+    if path == "-" {
+        return Box::new(io::stdin().lock());
+    }
+    let file = or_exit_from(path, File::open(path).map_err(GraphError::from));
+    return Box::new(io::BufReader::new(file));
+}
+
+/// Standard output for -
+fn create(path: &str) -> Box<dyn Write> {
+    if path == "-" {
+        return Box::new(io::BufWriter::new(io::stdout().lock()));
+    }
+    let file = or_exit_from(path, File::create(path).map_err(GraphError::from));
+    return Box::new(io::BufWriter::new(file));
+}
+
+fn write_metrics(graph: &Graph, param: String, directory: &Option<String>) {
+    if let Some(directory) = directory {
+        or_exit_from(
+            directory,
+            metrics(graph, param, Path::new(directory)).map_err(GraphError::from),
+        );
+    }
+}
+
+/// Graphs are read from the standard input
+fn or_exit<T>(result: Result<T, GraphError>) -> T {
+    return or_exit_from("stdin", result);
+}
+
+fn or_exit_from<T>(input: &str, result: Result<T, GraphError>) -> T {
+    return match result {
+        Ok(value) => value,
+        Err(error) => {
+            eprintln!("{}: {}", input, error);
+            process::exit(1);
+        }
+    };
+}
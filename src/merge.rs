@@ -0,0 +1,194 @@
+use crate::{haversine_distance, Edge, Graph, GraphError, Node, Operation, Order, Point};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Meters by degree of latitude, and of longitude at the equator
+const METERS_BY_DEGREE: f64 = 111_320.0;
+
+/// Unions graphs in the given order, nodes sharing an id are unified as long as they lie within
+/// tolerance meters of each other. A node closer than tolerance to a node of a previous graph is
+/// snapped onto it and its edges follow, edges which would become self-loops are dropped
+pub fn merge(graphs: Vec<Graph>, tolerance: f64) -> Result<Graph, GraphError> {
+    let mut merged = Graph::new();
+    let mut grid = Grid::new(tolerance);
+    for graph in graphs {
+        merged.header.extend(graph.header.iter().cloned());
+        merged.origins.extend(graph.origins.clone());
+        let mut snapped: HashMap<&str, String> = HashMap::new();
+        let mut inserted: Vec<&Node> = Vec::new();
+        for node in graph.sorted_nodes(Order::Insertion) {
+            let point = node.point()?;
+            if let Some(existing) = merged.nodes.get(&node.id) {
+                let distance = haversine_distance(&existing.point()?, &point);
+                if distance > tolerance {
+                    return Err(GraphError::InvalidRecord(format!(
+                        "node {} lies {} meters away from the node sharing its id",
+                        node.id, distance
+                    )));
+                }
+            } else if let Some(nearest_id) = grid.nearest(&merged, &point)? {
+                merged.derive(&nearest_id, Operation::Snap, &[&nearest_id, &node.id]);
+                merged.origins.remove(&node.id);
+                snapped.insert(&node.id, nearest_id);
+            } else {
+                inserted.push(node);
+            }
+        }
+        for node in inserted {
+            merged.insert_node(Node {
+                id: node.id.clone(),
+                latitude: node.latitude.clone(),
+                longitude: node.longitude.clone(),
+                neighbours: Vec::new(),
+            });
+            grid.insert(&node.id, &node.point()?);
+        }
+        for (u, v) in graph.sorted_pairs(Order::Insertion) {
+            let source = snapped.get(u.as_str()).unwrap_or(u);
+            let target = snapped.get(v.as_str()).unwrap_or(v);
+            if source == target {
+                continue;
+            }
+            for edge in graph.sorted_edges(u, v, Order::Insertion) {
+                let exists = merged
+                    .get_edges(source, target)
+                    .iter()
+                    .any(|other| other.id == edge.id);
+                if !exists {
+                    merged.insert_edge(
+                        source,
+                        target,
+                        Edge {
+                            id: edge.id.clone(),
+                            length: edge.length,
+                        },
+                    )?;
+                }
+            }
+        }
+    }
+    return Ok(merged);
+}
+
+/// Number of connected components
+pub fn components_count(graph: &Graph) -> usize {
+    let mut visited: HashSet<&str> = HashSet::new();
+    let mut count = 0;
+    for node_id in graph.nodes.keys() {
+        if visited.insert(node_id) {
+            count += 1;
+            let mut queue: VecDeque<&str> = VecDeque::from([node_id.as_str()]);
+            while let Some(current) = queue.pop_front() {
+                for neighbour_id in &graph.nodes[current].neighbours {
+                    if visited.insert(neighbour_id) {
+                        queue.push_back(neighbour_id);
+                    }
+                }
+            }
+        }
+    }
+    return count;
+}
+
+/// Buckets node ids by cells of tolerance meters to find snapping candidates
+pub(crate) struct Grid {
+    tolerance: f64,
+    cells: HashMap<(i64, i64), Vec<String>>,
+}
+
+impl Grid {
+    pub(crate) fn new(tolerance: f64) -> Grid {
+        return Grid {
+            tolerance,
+            cells: HashMap::new(),
+        };
+    }
+
+    fn size(&self) -> f64 {
+        return self.tolerance / METERS_BY_DEGREE;
+    }
+
+    fn cell(&self, point: &Point) -> (i64, i64) {
+        return (
+            (point.y / self.size()).floor() as i64,
+            (point.x / self.size()).floor() as i64,
+        );
+    }
+
+    pub(crate) fn insert(&mut self, node_id: &str, point: &Point) {
+        if self.tolerance > 0.0 {
+            let cell = self.cell(point);
+            self.cells
+                .entry(cell)
+                .or_default()
+                .push(node_id.to_string());
+        }
+    }
+
+    /// Closest node within tolerance meters of the point
+    pub(crate) fn nearest(
+        &self,
+        graph: &Graph,
+        point: &Point,
+    ) -> Result<Option<String>, GraphError> {
+        if self.tolerance <= 0.0 {
+            return Ok(None);
+        }
+        let (row, column) = self.cell(point);
+        // a degree of longitude shrinks with the cosine of the latitude
+        let span = (1.0 / point.y.to_radians().cos().abs().max(1e-6)).ceil() as i64 + 1;
+        let mut nearest: Option<(f64, &String)> = None;
+        for r in row - 2..=row + 2 {
+            for c in column - span..=column + span {
+                for node_id in self.cells.get(&(r, c)).into_iter().flatten() {
+                    let distance = haversine_distance(&graph.nodes[node_id].point()?, point);
+                    if distance <= self.tolerance
+                        && !matches!(nearest, Some((closest, _)) if closest <= distance)
+                    {
+                        nearest = Some((distance, node_id));
+                    }
+                }
+            }
+        }
+        return Ok(nearest.map(|(_, node_id)| node_id.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_merge() {
+        let west = Graph::_from("# ophois 0.3.0 extract separator='␟' area=\"West\"\n3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637486␟48.8275249␟2.348704\n3761637489␟3761637488\n3761637489␟3761637486", '␟').unwrap();
+        let east = Graph::_from("# ophois 0.3.0 extract separator='␟' area=\"East\"\n3761637486␟48.8275249␟2.348704\n1␟48.8275400␟2.3486690\n2268836829␟48.8276001␟2.3486802\n3761637486␟1␟east-link␟4.5\n1␟2268836829", '␟').unwrap();
+        let merged = merge(vec![west, east], 0.0).unwrap();
+        assert_eq!(merged.header.len(), 2);
+        assert_eq!(merged.nodes.len(), 5);
+        assert_eq!(merged.edges_count(), 4);
+        assert_eq!(components_count(&merged), 1);
+        assert!(merged.check_invariants().is_empty());
+        let west = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637486␟48.8275249␟2.348704\n3761637489␟3761637488\n3761637489␟3761637486", '␟').unwrap();
+        let east = Graph::_from("3761637486␟48.8275249␟2.348704\n1␟48.8275400␟2.3486690\n2268836829␟48.8276001␟2.3486802\n3761637486␟1␟east-link␟4.5\n1␟2268836829", '␟').unwrap();
+        let merged = merge(vec![west, east], 1.0).unwrap();
+        assert_eq!(merged.nodes.len(), 4);
+        assert!(!merged.nodes.contains_key("1"));
+        assert_eq!(merged.originals("3761637488"), vec!["1", "3761637488"]);
+        assert_eq!(
+            merged.get_edges("3761637486", "3761637488")[0].id,
+            "east-link"
+        );
+        assert!(merged.contains_link(("2268836829", "3761637488")));
+        assert!(merged.check_invariants().is_empty());
+    }
+
+    #[test]
+    fn test_merge_errors() {
+        let a = Graph::_from("1␟48.8275416␟2.3486683", '␟').unwrap();
+        let b = Graph::_from("1␟48.8275453␟2.348698", '␟').unwrap();
+        assert!(matches!(
+            merge(vec![a, b], 1.0),
+            Err(GraphError::InvalidRecord(_))
+        ));
+        let a = Graph::_from("1␟48.8275416␟2.3486683\n2␟48.8275453␟2.348698", '␟').unwrap();
+        assert_eq!(components_count(&a), 2);
+    }
+}
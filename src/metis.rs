@@ -0,0 +1,128 @@
+use crate::{Graph, GraphError, Order};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Writes the METIS adjacency format: nodes are numbered 1..n in the given order, parallel edges
+/// count once, self-loops are left out and, if weighted, links weigh the length of their shortest
+/// street in meters (at least 1)
+pub fn write_metis(
+    graph: &Graph,
+    writer: &mut impl Write,
+    order: Order,
+    weighted: bool,
+) -> Result<(), GraphError> {
+    let (nodes, indexes) = indexes(graph, order);
+    for line in &graph.header {
+        writeln!(writer, "% {}", line)?;
+    }
+    let links = graph.edges.keys().filter(|(u, v)| u != v).count();
+    write!(writer, "{} {}", nodes.len(), links)?;
+    writeln!(writer, "{}", if weighted { " 001" } else { "" })?;
+    for node_id in &nodes {
+        let mut neighbours: Vec<(usize, &String)> = graph.nodes[*node_id]
+            .neighbours
+            .iter()
+            .filter(|neighbour_id| neighbour_id != node_id)
+            .map(|neighbour_id| (indexes[neighbour_id.as_str()], neighbour_id))
+            .collect();
+        neighbours.sort();
+        let mut fields: Vec<String> = Vec::new();
+        for (index, neighbour_id) in neighbours {
+            fields.push(index.to_string());
+            if weighted {
+                let length = graph.length(node_id, neighbour_id)?;
+                fields.push((length.round() as u64).max(1).to_string());
+            }
+        }
+        writeln!(writer, "{}", fields.join(" "))?;
+    }
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Writes a symmetric Matrix Market coordinate matrix of the lengths of the shortest street of
+/// each link, nodes are numbered 1..n in the given order and only the lower triangle is written
+pub fn write_matrix_market(
+    graph: &Graph,
+    writer: &mut impl Write,
+    order: Order,
+) -> Result<(), GraphError> {
+    let (nodes, indexes) = indexes(graph, order);
+    writeln!(writer, "%%MatrixMarket matrix coordinate real symmetric")?;
+    for line in &graph.header {
+        writeln!(writer, "% {}", line)?;
+    }
+    writeln!(
+        writer,
+        "{} {} {}",
+        nodes.len(),
+        nodes.len(),
+        graph.edges.len()
+    )?;
+    let mut entries: Vec<(usize, usize, f64)> = Vec::new();
+    for (source, target) in graph.edges.keys() {
+        let (i, j) = (indexes[source.as_str()], indexes[target.as_str()]);
+        entries.push((i.max(j), i.min(j), graph.length(source, target)?));
+    }
+    entries.sort_by_key(|entry| (entry.1, entry.0));
+    for (i, j, length) in entries {
+        writeln!(writer, "{} {} {}", i, j, length)?;
+    }
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Node ids in the given order along with their 1-based index
+fn indexes(graph: &Graph, order: Order) -> (Vec<&String>, HashMap<&str, usize>) {
+    let nodes: Vec<&String> = graph
+        .sorted_nodes(order)
+        .into_iter()
+        .map(|node| &node.id)
+        .collect();
+    let indexes = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node_id)| (node_id.as_str(), index + 1))
+        .collect();
+    return (nodes, indexes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const GRAPH: &str = "3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637486␟48.8275249␟2.348704\n3761637489␟3761637488␟3761637488-3761637489␟2.5\n3761637489␟3761637488␟3761637488-3761637489~1␟0.2\n3761637489␟3761637486␟3761637486-3761637489␟12.7";
+
+    #[test]
+    fn test_write_metis() {
+        let graph = Graph::_from(GRAPH, '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_metis(&graph, &mut output, Order::Natural, false).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "3 2\n3\n3\n1 2\n");
+        let mut output: Vec<u8> = Vec::new();
+        write_metis(&graph, &mut output, Order::Natural, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "3 2 001\n3 13\n3 1\n1 13 2 1\n"
+        );
+        let graph = Graph::_from(
+            "a␟48.8275185␟2.3484976\nb␟48.8275416␟2.3486683\na␟a␟a-a␟4\na␟b",
+            '␟',
+        )
+        .unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_metis(&graph, &mut output, Order::Natural, false).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "2 1\n2\n1\n");
+    }
+
+    #[test]
+    fn test_write_matrix_market() {
+        let mut graph = Graph::_from(GRAPH, '␟').unwrap();
+        graph.annotate("discretize separator='␟' delta=5");
+        let mut output: Vec<u8> = Vec::new();
+        write_matrix_market(&graph, &mut output, Order::Natural).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            format!("%%MatrixMarket matrix coordinate real symmetric\n% ophois {} discretize separator='␟' delta=5\n3 3 2\n3 1 12.7\n3 2 0.2\n", env!("CARGO_PKG_VERSION"))
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::join_record;
 use quick_xml::de::from_str;
 use serde::Deserialize;
 use std::io;
@@ -21,18 +22,24 @@ struct NodeRef {
     r#ref: String,
 }
 
+/// Prints each OpenStreetMap node and way of the standard input on a single line
 pub fn format_xml() {
+    let mut output = io::BufWriter::new(io::stdout().lock());
+    write_formatted_xml(io::stdin().lock(), &mut output).expect("formatted xml written to stdout");
+}
+
+/// Writes each OpenStreetMap node and way of the input on a single line
+pub fn write_formatted_xml(input: impl BufRead, output: &mut impl Write) -> io::Result<()> {
     let mut data: String = "".to_owned();
     let mut way = false;
     let mut node = false;
-    let input = io::stdin();
-    for line in input.lock().lines() {
-        let row = line.unwrap().trim().to_string();
+    for line in input.lines() {
+        let row = line?.trim().to_string();
         if row.starts_with("<node") && row.ends_with("/>") {
-            println!("{}", row)
+            writeln!(output, "{}", row)?;
         }
         if row.starts_with("<way") && row.ends_with("/>") {
-            println!("{}", row)
+            writeln!(output, "{}", row)?;
         }
         if row.starts_with("<node") && !row.ends_with("/>") {
             node = true;
@@ -47,21 +54,19 @@ pub fn format_xml() {
         }
         if row.contains("</node>") {
             node = false;
-            println!("{}", data);
+            writeln!(output, "{}", data)?;
         }
         if row.contains("</way>") {
             way = false;
-            println!("{}", data);
+            writeln!(output, "{}", data)?;
         }
     }
+    return output.flush();
 }
 
 fn extract_node(line: String, separator: char) -> String {
     let node: Node = from_str(&line).unwrap();
-    return format!(
-        "{}{}{}{}{}",
-        node.id, separator, node.lat, separator, node.lon
-    );
+    return join_record(&[&node.id, &node.lat, &node.lon], separator);
 }
 
 fn extract_link(line: String, separator: char) -> String {
@@ -70,21 +75,26 @@ fn extract_link(line: String, separator: char) -> String {
     let mut output: Vec<String> = vec![];
     for i in 0..nodes.len() - 1 {
         output.push(format!(
-            "{}{}{}\n",
-            nodes[i].r#ref,
-            separator,
-            nodes[i + 1].r#ref
+            "{}\n",
+            join_record(&[&nodes[i].r#ref, &nodes[i + 1].r#ref], separator)
         ));
     }
     return output.join("");
 }
 
+/// Prints a formatted node as a node record and a formatted way as link records
 pub fn extract(line: String, separator: char) {
+    write_extracted(line, separator, &mut io::stdout().lock()).expect("records written to stdout");
+}
+
+/// Writes a formatted node as a node record and a formatted way as link records
+pub fn write_extracted(line: String, separator: char, output: &mut impl Write) -> io::Result<()> {
     if line.starts_with("<node") {
-        println!("{}", extract_node(line, separator));
+        writeln!(output, "{}", extract_node(line, separator))?;
     } else if line.starts_with("<way") {
-        print!("{}", extract_link(line, separator));
+        write!(output, "{}", extract_link(line, separator))?;
     }
+    return Ok(());
 }
 
 #[cfg(test)]
@@ -102,4 +112,18 @@ mod tests {
         let expected = "8807254574␟8807254575\n8807254575␟8507963130\n";
         assert!(extract_link(line.to_owned(), '␟') == expected)
     }
+
+    #[test]
+    fn test_write_formatted_xml() {
+        let xml = "<osm>\n  <node id=\"1\" lat=\"50.6\" lon=\"3.2\"/>\n  <way id=\"3\">\n    <nd ref=\"1\"/>\n    <nd ref=\"2\"/>\n  </way>\n</osm>\n";
+        let mut formatted: Vec<u8> = Vec::new();
+        write_formatted_xml(xml.as_bytes(), &mut formatted).unwrap();
+        let formatted = String::from_utf8(formatted).unwrap();
+        assert_eq!(formatted, "<node id=\"1\" lat=\"50.6\" lon=\"3.2\"/>\n<way id=\"3\"><nd ref=\"1\"/><nd ref=\"2\"/></way>\n");
+        let mut records: Vec<u8> = Vec::new();
+        for line in formatted.lines() {
+            write_extracted(line.to_string(), ' ', &mut records).unwrap();
+        }
+        assert_eq!(String::from_utf8(records).unwrap(), "1 50.6 3.2\n1 2\n");
+    }
 }
@@ -0,0 +1,153 @@
+use crate::{check_separator, join_record, natural_cmp, split_record, Graph, GraphError, Order};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+/// How ophois created a node
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Operation {
+    /// Midpoint of a link under delta, named "{a}-{b}"
+    Merge,
+    /// Split point of a link over delta, named "{link_id}:{i}/{n}"
+    Discretize,
+    /// Boundary point of a clipped link, named "{link_id}:boundary", ":boundary:1" or ":boundary:2"
+    Clip,
+    /// Node of a merged graph onto which close nodes of the next graphs were snapped
+    Snap,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Operation::Merge => "merge",
+            Operation::Discretize => "discretize",
+            Operation::Clip => "clip",
+            Operation::Snap => "snap",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Operation {
+    type Err = String;
+
+    fn from_str(operation: &str) -> Result<Operation, String> {
+        return match operation {
+            "merge" => Ok(Operation::Merge),
+            "discretize" => Ok(Operation::Discretize),
+            "clip" => Ok(Operation::Clip),
+            "snap" => Ok(Operation::Snap),
+            _ => Err(format!("unknown operation {}", operation)),
+        };
+    }
+}
+
+/// Last operation which produced a node and the original (OpenStreetMap) nodes it stands for
+#[derive(Clone, PartialEq, Debug)]
+pub struct Origin {
+    pub operation: Operation,
+    /// Sorted in natural id order, without duplicates
+    pub nodes: Vec<String>,
+}
+
+impl Graph {
+    /// Original nodes a node stands for, the node itself if it was not created by ophois
+    pub fn originals(&self, node_id: &str) -> Vec<String> {
+        return match self.origins.get(node_id) {
+            Some(origin) => origin.nodes.clone(),
+            None => vec![node_id.to_string()],
+        };
+    }
+
+    /// Records that the node stands for the original nodes of the given nodes
+    pub fn derive(&mut self, node_id: &str, operation: Operation, from: &[&str]) {
+        let mut nodes: Vec<String> = from
+            .iter()
+            .flat_map(|id| self.originals(id))
+            .collect::<HashSet<String>>()
+            .into_iter()
+            .collect();
+        nodes.sort_by(|a, b| natural_cmp(a, b));
+        self.origins
+            .insert(node_id.to_string(), Origin { operation, nodes });
+    }
+
+    /// Writes one line per created node of the graph: id, operation then its original nodes
+    pub fn write_origins(&self, output: &mut impl Write, separator: char) -> io::Result<()> {
+        check_separator(separator)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error.to_string()))?;
+        for node in self.sorted_nodes(Order::Natural) {
+            if let Some(origin) = self.origins.get(&node.id) {
+                let operation = origin.operation.to_string();
+                let mut record: Vec<&str> = vec![&node.id, &operation];
+                record.extend(origin.nodes.iter().map(|original| original.as_str()));
+                writeln!(output, "{}", join_record(&record, separator))?;
+            }
+        }
+        return output.flush();
+    }
+
+    /// Reads origins written by write_origins, so that they carry over from a command to the next
+    pub fn read_origins(&mut self, input: impl BufRead, separator: char) -> Result<(), GraphError> {
+        for (index, line) in input.lines().enumerate() {
+            let line = line?;
+            let line_error = |error: GraphError| GraphError::Line {
+                number: index + 1,
+                content: line.clone(),
+                error: Box::new(error),
+            };
+            let data = split_record(&line, separator).map_err(line_error)?;
+            let operation = match data.get(1).map(|name| name.parse::<Operation>()) {
+                Some(Ok(operation)) if data.len() > 2 => operation,
+                _ => {
+                    return Err(line_error(GraphError::InvalidRecord(
+                        "expected id operation original_id…".to_string(),
+                    )))
+                }
+            };
+            let nodes = data[2..].to_vec();
+            self.origins
+                .insert(data[0].clone(), Origin { operation, nodes });
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_origins() {
+        let mut graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637486␟48.8275249␟2.348704\n3761637488-3761637489␟48.82754345␟2.34868315\n3761637486-3761637488-3761637489␟48.8275341␟2.3486935", '␟').unwrap();
+        graph.derive(
+            "3761637488-3761637489",
+            Operation::Merge,
+            &["3761637488", "3761637489"],
+        );
+        graph.derive(
+            "3761637486-3761637488-3761637489",
+            Operation::Merge,
+            &["3761637486", "3761637488-3761637489"],
+        );
+        assert_eq!(
+            graph.originals("3761637486-3761637488-3761637489"),
+            vec!["3761637486", "3761637488", "3761637489"]
+        );
+        assert_eq!(graph.originals("3761637486"), vec!["3761637486"]);
+        graph.remove_node("3761637488-3761637489").unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        graph.write_origins(&mut output, '␟').unwrap();
+        assert_eq!(
+            String::from_utf8(output.clone()).unwrap(),
+            "3761637486-3761637488-3761637489␟merge␟3761637486␟3761637488␟3761637489\n"
+        );
+        let mut copy = Graph::new();
+        copy.read_origins(output.as_slice(), '␟').unwrap();
+        assert_eq!(copy.origins, graph.origins);
+        assert!(matches!(
+            copy.read_origins("1␟split␟2".as_bytes(), '␟'),
+            Err(GraphError::Line { number: 1, .. })
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+use crate::{escape_xml, Graph, GraphError, Order};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Writes an OpenStreetMap XML file for editors such as JOSM: nodes get negative ids -1..-n and
+/// an ophois:id tag, each edge becomes a way tagged with its ophois:id and ophois:length.
+/// Uploading is disabled so that the file never reaches OpenStreetMap by mistake
+pub fn write_osm(graph: &Graph, writer: &mut impl Write, order: Order) -> Result<(), GraphError> {
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        writer,
+        "<osm version=\"0.6\" generator=\"ophois {}\" upload=\"never\">",
+        env!("CARGO_PKG_VERSION")
+    )?;
+    let mut ids: HashMap<&str, i64> = HashMap::new();
+    let mut next_id: i64 = -1;
+    for node in graph.sorted_nodes(order) {
+        ids.insert(&node.id, next_id);
+        writeln!(
+            writer,
+            "  <node id=\"{}\" action=\"modify\" visible=\"true\" lat=\"{}\" lon=\"{}\">",
+            next_id,
+            escape_xml(&node.latitude),
+            escape_xml(&node.longitude)
+        )?;
+        write_tag(writer, "ophois:id", &node.id)?;
+        writeln!(writer, "  </node>")?;
+        next_id -= 1;
+    }
+    for (source, target) in graph.sorted_pairs(order) {
+        for edge in graph.sorted_edges(source, target, order) {
+            writeln!(
+                writer,
+                "  <way id=\"{}\" action=\"modify\" visible=\"true\">",
+                next_id
+            )?;
+            writeln!(writer, "    <nd ref=\"{}\"/>", ids[source.as_str()])?;
+            writeln!(writer, "    <nd ref=\"{}\"/>", ids[target.as_str()])?;
+            write_tag(writer, "ophois:id", &edge.id)?;
+            write_tag(writer, "ophois:length", &edge.length.to_string())?;
+            writeln!(writer, "  </way>")?;
+            next_id -= 1;
+        }
+    }
+    writeln!(writer, "</osm>")?;
+    writer.flush()?;
+    return Ok(());
+}
+
+fn write_tag(writer: &mut impl Write, key: &str, value: &str) -> Result<(), GraphError> {
+    writeln!(
+        writer,
+        "    <tag k=\"{}\" v=\"{}\"/>",
+        escape_xml(key),
+        escape_xml(value)
+    )?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_write_osm() {
+        let graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637486␟48.8275249␟2.348704\n3761637489␟3761637488\n3761637489␟3761637486␟a<b␟2.5", '␟').unwrap();
+        let mut output: Vec<u8> = Vec::new();
+        write_osm(&graph, &mut output, Order::Natural).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("<node id=\"-1\" action=\"modify\" visible=\"true\" lat=\"48.8275249\" lon=\"2.348704\">\n    <tag k=\"ophois:id\" v=\"3761637486\"/>\n  </node>"));
+        assert!(output.contains("<way id=\"-4\" action=\"modify\" visible=\"true\">\n    <nd ref=\"-1\"/>\n    <nd ref=\"-3\"/>\n    <tag k=\"ophois:id\" v=\"a&lt;b\"/>\n    <tag k=\"ophois:length\" v=\"2.5\"/>\n  </way>"));
+        assert_eq!(output.matches("<way ").count(), 2);
+        assert!(output.ends_with("</osm>\n"));
+    }
+}
@@ -1,6 +1,7 @@
 use std::fs::File;
 use std::io::prelude::*;
 
+/// Saves the result of an overpass QL query on an area to {city}.osm
 #[tokio::main]
 pub async fn download_map(
     city: String,
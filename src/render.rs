@@ -0,0 +1,286 @@
+use crate::{natural_cmp, Graph, GraphError, Order, Point};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::str::FromStr;
+
+/// Colours nodes and links are picked from, in order
+const PALETTE: [&str; 8] = [
+    "#1f77b4", "#d62728", "#2ca02c", "#ff7f0e", "#9467bd", "#8c564b", "#e377c2", "#17becf",
+];
+const LINK_COLOUR: &str = "#555555";
+const OVERLAY_COLOUR: &str = "#cccccc";
+const MARGIN: f64 = 10.0;
+
+/// How nodes and links are coloured, there is no colouring by tag as the graph keeps no OpenStreetMap tags
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Colouring {
+    Uniform,
+    /// Nodes by their degree, dead ends first
+    Degree,
+    /// Nodes and links by their connected component
+    Component,
+}
+
+impl FromStr for Colouring {
+    type Err = String;
+
+    fn from_str(colouring: &str) -> Result<Colouring, String> {
+        return match colouring {
+            "uniform" => Ok(Colouring::Uniform),
+            "degree" => Ok(Colouring::Degree),
+            "component" => Ok(Colouring::Component),
+            _ => Err(format!(
+                "unknown colouring {}, expected uniform, degree or component",
+                colouring
+            )),
+        };
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Style {
+    /// Width of the picture in pixels, its height follows the graph
+    pub width: f64,
+    pub colouring: Colouring,
+    pub node_radius: f64,
+    pub link_width: f64,
+    /// Links are drawn from half to twice link_width, from the shortest to the longest
+    pub width_by_length: bool,
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        return Style {
+            width: 1000.0,
+            colouring: Colouring::Uniform,
+            node_radius: 2.0,
+            link_width: 1.0,
+            width_by_length: false,
+        };
+    }
+}
+
+/// Draws the graph in SVG, over the links of another graph (such as the graph before
+/// simplification) if any. Coordinates are projected equirectangularly around the mean latitude
+pub fn write_svg(
+    graph: &Graph,
+    overlay: Option<&Graph>,
+    style: &Style,
+    writer: &mut impl Write,
+) -> Result<(), GraphError> {
+    let points: Vec<Point> = graph
+        .nodes
+        .values()
+        .chain(overlay.iter().flat_map(|overlay| overlay.nodes.values()))
+        .map(|node| node.point())
+        .collect::<Result<_, _>>()?;
+    let projection = Projection::new(&points, style.width);
+    writeln!(
+        writer,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{:.2}\" viewBox=\"0 0 {} {:.2}\">",
+        style.width, projection.height, style.width, projection.height
+    )?;
+    writeln!(
+        writer,
+        "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>"
+    )?;
+    if let Some(overlay) = overlay {
+        writeln!(
+            writer,
+            "<g stroke=\"{}\" stroke-width=\"{}\">",
+            OVERLAY_COLOUR, style.link_width
+        )?;
+        for (source, target) in overlay.sorted_pairs(Order::Natural) {
+            write_line(writer, &projection, overlay, source, target, None, None)?;
+        }
+        writeln!(writer, "</g>")?;
+    }
+    let components = components(graph);
+    let longest = graph
+        .edges
+        .values()
+        .flatten()
+        .map(|edge| edge.length)
+        .fold(0.0, f64::max);
+    writeln!(
+        writer,
+        "<g stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\">",
+        LINK_COLOUR, style.link_width
+    )?;
+    for (source, target) in graph.sorted_pairs(Order::Natural) {
+        let colour = match style.colouring {
+            Colouring::Component => Some(PALETTE[components[source.as_str()] % PALETTE.len()]),
+            _ => None,
+        };
+        // parallel edges overlap, the longest sets the width
+        let length = graph
+            .get_edges(source, target)
+            .iter()
+            .map(|edge| edge.length)
+            .fold(0.0, f64::max);
+        let width = if style.width_by_length && longest > 0.0 {
+            Some(style.link_width * (0.5 + 1.5 * length / longest))
+        } else {
+            None
+        };
+        write_line(writer, &projection, graph, source, target, colour, width)?;
+    }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "<g fill=\"{}\">", PALETTE[0])?;
+    for node in graph.sorted_nodes(Order::Natural) {
+        let (x, y) = projection.project(&node.point()?);
+        let colour = match style.colouring {
+            Colouring::Uniform => PALETTE[0],
+            Colouring::Degree => PALETTE[node.neighbours.len().saturating_sub(1) % PALETTE.len()],
+            Colouring::Component => PALETTE[components[node.id.as_str()] % PALETTE.len()],
+        };
+        writeln!(
+            writer,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{}\" fill=\"{}\"/>",
+            x, y, style.node_radius, colour
+        )?;
+    }
+    writeln!(writer, "</g>")?;
+    writeln!(writer, "</svg>")?;
+    writer.flush()?;
+    return Ok(());
+}
+
+fn write_line(
+    writer: &mut impl Write,
+    projection: &Projection,
+    graph: &Graph,
+    source: &str,
+    target: &str,
+    colour: Option<&str>,
+    width: Option<f64>,
+) -> Result<(), GraphError> {
+    let (x1, y1) = projection.project(&graph.get_node(source)?.point()?);
+    let (x2, y2) = projection.project(&graph.get_node(target)?.point()?);
+    write!(
+        writer,
+        "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\"",
+        x1, y1, x2, y2
+    )?;
+    if let Some(colour) = colour {
+        write!(writer, " stroke=\"{}\"", colour)?;
+    }
+    if let Some(width) = width {
+        write!(writer, " stroke-width=\"{:.2}\"", width)?;
+    }
+    writeln!(writer, "/>")?;
+    return Ok(());
+}
+
+/// Index of the component of each node, components numbered from their first node in natural id order
+fn components(graph: &Graph) -> HashMap<&str, usize> {
+    let mut components: HashMap<&str, usize> = HashMap::new();
+    let mut nodes: Vec<&String> = graph.nodes.keys().collect();
+    nodes.sort_by(|a, b| natural_cmp(a, b));
+    let mut index = 0;
+    for root in nodes {
+        if components.contains_key(root.as_str()) {
+            continue;
+        }
+        components.insert(root, index);
+        let mut queue: VecDeque<&str> = VecDeque::from([root.as_str()]);
+        while let Some(node_id) = queue.pop_front() {
+            for neighbour_id in &graph.nodes[node_id].neighbours {
+                if !components.contains_key(neighbour_id.as_str()) {
+                    components.insert(neighbour_id, index);
+                    queue.push_back(neighbour_id);
+                }
+            }
+        }
+        index += 1;
+    }
+    return components;
+}
+
+struct Projection {
+    west: f64,
+    north: f64,
+    /// Shrinks longitudes so that a meter east is as long as a meter north
+    ratio: f64,
+    scale: f64,
+    height: f64,
+}
+
+impl Projection {
+    fn new(points: &[Point], width: f64) -> Projection {
+        let west = points
+            .iter()
+            .map(|point| point.x)
+            .fold(f64::INFINITY, f64::min);
+        let east = points
+            .iter()
+            .map(|point| point.x)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let south = points
+            .iter()
+            .map(|point| point.y)
+            .fold(f64::INFINITY, f64::min);
+        let north = points
+            .iter()
+            .map(|point| point.y)
+            .fold(f64::NEG_INFINITY, f64::max);
+        if points.is_empty() {
+            return Projection {
+                west: 0.0,
+                north: 0.0,
+                ratio: 1.0,
+                scale: 1.0,
+                height: 2.0 * MARGIN,
+            };
+        }
+        let ratio = ((south + north) / 2.0).to_radians().cos();
+        let extent = ((east - west) * ratio).max(north - south);
+        let scale = if extent > 0.0 {
+            (width - 2.0 * MARGIN) / extent
+        } else {
+            1.0
+        };
+        return Projection {
+            west,
+            north,
+            ratio,
+            scale,
+            height: (north - south) * scale + 2.0 * MARGIN,
+        };
+    }
+
+    fn project(&self, point: &Point) -> (f64, f64) {
+        return (
+            MARGIN + (point.x - self.west) * self.ratio * self.scale,
+            MARGIN + (self.north - point.y) * self.scale,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_write_svg() {
+        let graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637486␟48.8275249␟2.348704\n2268836829␟48.8276001␟2.3486802\n3761637489␟3761637488\n3761637489␟3761637486", '␟').unwrap();
+        let before = Graph::_from("3761637488␟48.8275416␟2.3486683\n3758221295␟48.8275185␟2.3484976\n3758221295␟3761637488", '␟').unwrap();
+        let style = Style {
+            colouring: Colouring::Component,
+            width_by_length: true,
+            ..Style::default()
+        };
+        let mut output: Vec<u8> = Vec::new();
+        write_svg(&graph, Some(&before), &style, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"1000\""));
+        assert_eq!(output.matches("<circle ").count(), 4);
+        assert_eq!(output.matches("<line ").count(), 3);
+        assert!(output.contains(&format!("<g stroke=\"{}\"", OVERLAY_COLOUR)));
+        // the west most node of the overlay lies on the left margin
+        assert!(output.contains("x1=\"10.00\""));
+        // the isolated node comes first in natural id order, the linked nodes form the second component
+        assert!(output.contains(&format!("fill=\"{}\"/>", PALETTE[1])));
+        assert!(output.contains("stroke-width=\"2.00\""));
+        assert!(output.ends_with("</svg>\n"));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{natural_cmp, Edge, Graph, GraphError, Node, Order};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+
+/// Order in which nodes receive their new ids
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Numbering {
+    Natural,
+    /// Position along a Hilbert curve over the bounding box of the graph, close nodes get close ids
+    Hilbert,
+    /// Breadth first from the first node of each component in natural id order
+    Bfs,
+}
+
+impl FromStr for Numbering {
+    type Err = String;
+
+    fn from_str(numbering: &str) -> Result<Numbering, String> {
+        return match numbering {
+            "natural" => Ok(Numbering::Natural),
+            "hilbert" => Ok(Numbering::Hilbert),
+            "bfs" => Ok(Numbering::Bfs),
+            _ => Err(format!(
+                "unknown numbering {}, expected natural, hilbert or bfs",
+                numbering
+            )),
+        };
+    }
+}
+
+/// Renames nodes 0..n and edges after their new nodes, returns the graph along with the
+/// (old id, new id) pairs in new id order
+pub fn renumber(
+    graph: Graph,
+    numbering: Numbering,
+) -> Result<(Graph, Vec<(String, String)>), GraphError> {
+    let mapping: Vec<(String, String)> = numbered(&graph, numbering)?
+        .into_iter()
+        .enumerate()
+        .map(|(index, node_id)| (node_id, index.to_string()))
+        .collect();
+    let ids: HashMap<&str, &str> = mapping
+        .iter()
+        .map(|(old, new)| (old.as_str(), new.as_str()))
+        .collect();
+    let mut renumbered = Graph::new();
+    renumbered.header = graph.header.clone();
+    for (old, new) in &mapping {
+        let node = graph.get_node(old)?;
+        renumbered.insert_node(Node {
+            id: new.clone(),
+            latitude: node.latitude.clone(),
+            longitude: node.longitude.clone(),
+            neighbours: Vec::new(),
+        });
+        if let Some(origin) = graph.origins.get(old) {
+            renumbered.origins.insert(new.clone(), origin.clone());
+        }
+    }
+    let mut pairs: Vec<(&str, &str, &(String, String))> = graph
+        .edges
+        .keys()
+        .map(|pair| {
+            let (u, v) = (ids[pair.0.as_str()], ids[pair.1.as_str()]);
+            let (u, v) = if natural_cmp(u, v).is_le() {
+                (u, v)
+            } else {
+                (v, u)
+            };
+            (u, v, pair)
+        })
+        .collect();
+    pairs.sort_by(|a, b| natural_cmp(a.0, b.0).then(natural_cmp(a.1, b.1)));
+    for (u, v, pair) in pairs {
+        for edge in graph.sorted_edges(&pair.0, &pair.1, Order::Natural) {
+            let edge = Edge {
+                id: renumbered.link_id(u, v),
+                length: edge.length,
+            };
+            renumbered.insert_edge(u, v, edge)?;
+        }
+    }
+    return Ok((renumbered, mapping));
+}
+
+fn numbered(graph: &Graph, numbering: Numbering) -> Result<Vec<String>, GraphError> {
+    let mut nodes: Vec<String> = graph
+        .sorted_nodes(Order::Natural)
+        .iter()
+        .map(|node| node.id.clone())
+        .collect();
+    match numbering {
+        Numbering::Natural => {}
+        Numbering::Hilbert => {
+            let points: Vec<(f64, f64)> = nodes
+                .iter()
+                .map(|node_id| {
+                    let point = graph.nodes[node_id].point()?;
+                    Ok((point.x, point.y))
+                })
+                .collect::<Result<_, GraphError>>()?;
+            let bounds = |coordinate: fn(&(f64, f64)) -> f64| {
+                points
+                    .iter()
+                    .map(coordinate)
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                        (min.min(value), max.max(value))
+                    })
+            };
+            let (x_bounds, y_bounds) = (bounds(|point| point.0), bounds(|point| point.1));
+            let cell = |value: f64, (min, max): (f64, f64)| -> u32 {
+                if max > min {
+                    ((value - min) / (max - min) * HILBERT_SIDE as f64)
+                        .min(HILBERT_SIDE as f64 - 1.0) as u32
+                } else {
+                    0
+                }
+            };
+            let positions: HashMap<&String, u64> = nodes
+                .iter()
+                .zip(&points)
+                .map(|(node_id, point)| {
+                    let position = hilbert(cell(point.0, x_bounds), cell(point.1, y_bounds));
+                    (node_id, position)
+                })
+                .collect();
+            let mut sorted = nodes.clone();
+            // the sort is stable, nodes sharing a cell stay in natural id order
+            sorted.sort_by_key(|node_id| positions[node_id]);
+            nodes = sorted;
+        }
+        Numbering::Bfs => {
+            let mut visited: HashSet<String> = HashSet::new();
+            let mut order: Vec<String> = Vec::new();
+            for root in &nodes {
+                if !visited.insert(root.clone()) {
+                    continue;
+                }
+                let mut queue: VecDeque<String> = VecDeque::from([root.clone()]);
+                while let Some(node_id) = queue.pop_front() {
+                    let mut neighbours = graph.nodes[&node_id].neighbours.clone();
+                    neighbours.sort_by(|a, b| natural_cmp(a, b));
+                    for neighbour_id in neighbours {
+                        if visited.insert(neighbour_id.clone()) {
+                            queue.push_back(neighbour_id);
+                        }
+                    }
+                    order.push(node_id);
+                }
+            }
+            nodes = order;
+        }
+    }
+    return Ok(nodes);
+}
+
+/// Cells by side of the Hilbert curve grid
+const HILBERT_SIDE: u32 = 1 << 16;
+
+/// Distance along the Hilbert curve of the cell (x, y)
+fn hilbert(mut x: u32, mut y: u32) -> u64 {
+    let mut distance: u64 = 0;
+    let mut side = HILBERT_SIDE / 2;
+    while side > 0 {
+        let rx = (x & side > 0) as u32;
+        let ry = (y & side > 0) as u32;
+        distance += side as u64 * side as u64 * ((3 * rx) ^ ry) as u64;
+        if ry == 0 {
+            if rx == 1 {
+                x = HILBERT_SIDE - 1 - x;
+                y = HILBERT_SIDE - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        side /= 2;
+    }
+    return distance;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_renumber() {
+        let input = "3758221295␟48.8275185␟2.3484976\n3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n2268836829␟48.8276001␟2.3486802\n3761637486␟48.8275249␟2.348704\n3758221295␟3761637488\n3761637488␟3761637489\n3761637489␟2268836829\n3761637489␟3761637486\n3761637489␟3761637486␟second␟3.5";
+        for numbering in [Numbering::Natural, Numbering::Hilbert, Numbering::Bfs] {
+            let graph = Graph::_from(input, '␟').unwrap();
+            let (renumbered, mapping) = renumber(graph, numbering).unwrap();
+            assert_eq!(renumbered.nodes.len(), 5);
+            assert_eq!(renumbered.edges_count(), 5);
+            assert!(renumbered.check_invariants().is_empty());
+            let ids: Vec<&str> = mapping.iter().map(|(_, new)| new.as_str()).collect();
+            assert_eq!(ids, vec!["0", "1", "2", "3", "4"]);
+            let new = |old: &str| mapping.iter().find(|(id, _)| id == old).unwrap().1.clone();
+            assert_eq!(
+                renumbered
+                    .get_edges(&new("3761637489"), &new("3761637486"))
+                    .len(),
+                2
+            );
+        }
+        let graph = Graph::_from(input, '␟').unwrap();
+        let (_, mapping) = renumber(graph, Numbering::Bfs).unwrap();
+        let old: Vec<&str> = mapping.iter().map(|(old, _)| old.as_str()).collect();
+        assert_eq!(
+            old,
+            vec![
+                "2268836829",
+                "3761637489",
+                "3761637486",
+                "3761637488",
+                "3758221295"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hilbert() {
+        let side = HILBERT_SIDE - 1;
+        assert_eq!(hilbert(0, 0), 0);
+        assert_eq!(hilbert(side, 0), (HILBERT_SIDE as u64).pow(2) - 1);
+        assert!(hilbert(0, side) < hilbert(side, side));
+        assert_eq!(hilbert(1, 1), 2);
+        assert_eq!(hilbert(1, 0) + hilbert(0, 1), 4);
+    }
+}
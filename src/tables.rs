@@ -0,0 +1,95 @@
+use crate::{Graph, GraphError, Order};
+use std::io::Write;
+
+/// Writes the nodes table: a header line then id, latitude, longitude and degree of each node
+pub fn write_nodes_table(
+    graph: &Graph,
+    writer: &mut impl Write,
+    order: Order,
+    delimiter: char,
+) -> Result<(), GraphError> {
+    write_row(
+        writer,
+        delimiter,
+        &["id", "latitude", "longitude", "degree"],
+    )?;
+    for node in graph.sorted_nodes(order) {
+        write_row(
+            writer,
+            delimiter,
+            &[
+                &node.id,
+                &node.latitude,
+                &node.longitude,
+                &node.neighbours.len().to_string(),
+            ],
+        )?;
+    }
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Writes the edges table: a header line then source, target, length and id of each edge,
+/// parallel edges have a row each
+pub fn write_edges_table(
+    graph: &Graph,
+    writer: &mut impl Write,
+    order: Order,
+    delimiter: char,
+) -> Result<(), GraphError> {
+    write_row(writer, delimiter, &["source", "target", "length", "id"])?;
+    for (source, target) in graph.sorted_pairs(order) {
+        for edge in graph.sorted_edges(source, target, order) {
+            write_row(
+                writer,
+                delimiter,
+                &[source, target, &edge.length.to_string(), &edge.id],
+            )?;
+        }
+    }
+    writer.flush()?;
+    return Ok(());
+}
+
+/// Fields holding the delimiter, a quote or a line break are quoted as in RFC 4180, quotes doubled
+fn write_row(writer: &mut impl Write, delimiter: char, fields: &[&str]) -> Result<(), GraphError> {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([delimiter, '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+        .collect();
+    writeln!(writer, "{}", fields.join(&delimiter.to_string()))?;
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_write_tables() {
+        let graph = Graph::_from("3761637488␟48.8275416␟2.3486683\n3761637489␟48.8275453␟2.348698\n3761637486␟48.8275249␟2.348704\n3761637489␟3761637488␟3761637488-3761637489␟2.5\n3761637489␟3761637488␟rue \"des\", cailles␟3\n3761637489␟3761637486", '␟').unwrap();
+        let mut nodes: Vec<u8> = Vec::new();
+        write_nodes_table(&graph, &mut nodes, Order::Natural, ',').unwrap();
+        assert_eq!(
+            String::from_utf8(nodes).unwrap(),
+            "id,latitude,longitude,degree\n3761637486,48.8275249,2.348704,1\n3761637488,48.8275416,2.3486683,1\n3761637489,48.8275453,2.348698,2\n"
+        );
+        let mut edges: Vec<u8> = Vec::new();
+        write_edges_table(&graph, &mut edges, Order::Natural, ',').unwrap();
+        let edges = String::from_utf8(edges).unwrap();
+        assert!(edges.starts_with("source,target,length,id\n3761637486,3761637489,"));
+        assert!(edges.ends_with(
+            "3761637488,3761637489,2.5,3761637488-3761637489\n3761637488,3761637489,3,\"rue \"\"des\"\", cailles\"\n"
+        ));
+        let mut edges: Vec<u8> = Vec::new();
+        write_edges_table(&graph, &mut edges, Order::Natural, '\t').unwrap();
+        assert!(String::from_utf8(edges)
+            .unwrap()
+            .ends_with("\t3\t\"rue \"\"des\"\", cailles\"\n"));
+    }
+}
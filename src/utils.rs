@@ -1,3 +1,10 @@
+use crate::GraphError;
+use std::cmp::Ordering;
+
+/// Separators which would make escaped fields ambiguous
+const RESERVED_SEPARATORS: [char; 5] = ['\\', '\n', '\r', 'n', 'r'];
+
+/// Orders a pair of ids so that both directions of a link share the same key
 pub fn determinist(u: String, v: String) -> (String, String) {
     return if u < v {
         (u.clone(), v.clone())
@@ -5,3 +12,176 @@ pub fn determinist(u: String, v: String) -> (String, String) {
         (v.clone(), u.clone())
     };
 }
+
+/// Header line of a graph file produced by a command
+pub fn provenance(command: &str) -> String {
+    return format!("ophois {} {}", env!("CARGO_PKG_VERSION"), command);
+}
+
+/// Escapes text to be written in XML content or attribute values
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+/// Fails if the separator cannot tell fields apart once escaped
+pub fn check_separator(separator: char) -> Result<(), GraphError> {
+    if RESERVED_SEPARATORS.contains(&separator) {
+        return Err(GraphError::InvalidRecord(format!(
+            "separator {:?} is reserved by escapes",
+            separator
+        )));
+    }
+    return Ok(());
+}
+
+/// Escapes a field of a separated record: a backslash precedes backslashes, separators and a
+/// leading # (which would start a header line), line breaks are written \n and \r
+pub fn escape_field(field: &str, separator: char) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for (index, c) in field.chars().enumerate() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            '#' if index == 0 => escaped.push_str("\\#"),
+            c if c == separator => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+/// Escapes and joins the fields of a record
+pub fn join_record(fields: &[&str], separator: char) -> String {
+    return fields
+        .iter()
+        .map(|field| escape_field(field, separator))
+        .collect::<Vec<String>>()
+        .join(&separator.to_string());
+}
+
+/// Splits a record on unescaped separators and unescapes its fields, fails on any other escape
+pub fn split_record(line: &str, separator: char) -> Result<Vec<String>, GraphError> {
+    check_separator(separator)?;
+    let mut fields: Vec<String> = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("at least one field");
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => field.push('\n'),
+                Some('r') => field.push('\r'),
+                Some(escaped) if escaped == '\\' || escaped == '#' || escaped == separator => {
+                    field.push(escaped)
+                }
+                Some(escaped) => {
+                    return Err(GraphError::InvalidRecord(format!(
+                        "invalid escape \\{}",
+                        escaped
+                    )))
+                }
+                None => {
+                    return Err(GraphError::InvalidRecord(
+                        "dangling escape at end of line".to_string(),
+                    ))
+                }
+            },
+            c if c == separator => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    return Ok(fields);
+}
+
+/// Compares ids chunk by chunk, runs of digits by their numeric value: 2 < 10 < 10-2 < 10-10
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    while !a.is_empty() && !b.is_empty() {
+        let a_digits = a.starts_with(|c: char| c.is_ascii_digit());
+        let b_digits = b.starts_with(|c: char| c.is_ascii_digit());
+        let a_end = a
+            .find(|c: char| c.is_ascii_digit() != a_digits)
+            .unwrap_or(a.len());
+        let b_end = b
+            .find(|c: char| c.is_ascii_digit() != b_digits)
+            .unwrap_or(b.len());
+        let (a_chunk, b_chunk) = (&a[..a_end], &b[..b_end]);
+        let ordering = if a_digits && b_digits {
+            let a_number = a_chunk.trim_start_matches('0');
+            let b_number = b_chunk.trim_start_matches('0');
+            a_number
+                .len()
+                .cmp(&b_number.len())
+                .then(a_number.cmp(b_number))
+                .then(a_chunk.len().cmp(&b_chunk.len()))
+        } else {
+            a_chunk.cmp(b_chunk)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+        a = &a[a_end..];
+        b = &b[b_end..];
+    }
+    return a.len().cmp(&b.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_natural_cmp() {
+        let mut ids = vec![
+            "10-10",
+            "10",
+            "2",
+            "10-2",
+            "1852590201-92192237:10/14",
+            "1852590201-92192237:9/14",
+            "b",
+            "a",
+        ];
+        ids.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            ids,
+            vec![
+                "2",
+                "10",
+                "10-2",
+                "10-10",
+                "1852590201-92192237:9/14",
+                "1852590201-92192237:10/14",
+                "a",
+                "b"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_record() {
+        let record = join_record(&["1-2:1/3", "a␟b", "c\\"], '␟');
+        assert_eq!(record, "1-2:1/3␟a\\␟b␟c\\\\");
+        assert_eq!(
+            split_record(&record, '␟').unwrap(),
+            vec!["1-2:1/3", "a␟b", "c\\"]
+        );
+        assert_eq!(split_record("", '␟').unwrap(), vec![""]);
+        assert!(split_record("a\\-b", '␟').is_err());
+        assert!(check_separator('n').is_err());
+        assert!(check_separator(' ').is_ok());
+    }
+}